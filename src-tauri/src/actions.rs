@@ -0,0 +1,435 @@
+// Prompt construction and execution for the built-in and custom text actions
+use crate::budget;
+use crate::chunking::{self, Chunks};
+use crate::error::CallError;
+use crate::estimate;
+use crate::external_command::{self, CommandSpec};
+use crate::models;
+use crate::openrouter::{self, Model};
+use crate::routing;
+use crate::sampling;
+use crate::scripting::{self, ScriptContext, Stage};
+use crate::transforms::{self, Transform};
+use crate::usage::{self, Usage};
+use futures_util::{stream, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use std::sync::RwLock;
+
+pub const DEFAULT_MAX_TOKENS: u32 = 2000;
+pub const DEFAULT_TONE: &str = "professional";
+
+// Id, title and description of the actions that ship with the app
+const BUILT_IN_ACTIONS: &[(&str, &str, &str)] = &[
+    ("proofread", "Proofread", "Correct grammar, spelling, punctuation and clarity"),
+    ("tone", "Change Tone", "Rewrite the text in a different tone"),
+    ("draft", "Draft", "Improve and expand the text"),
+];
+
+// User-defined actions, listed after the built-in ones
+static CUSTOM_ACTIONS: RwLock<Vec<CustomAction>> = RwLock::new(Vec::new());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionKind {
+    Ai,
+    Command,
+    // Built-in transform that runs locally, without a model
+    Local,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ActionInfo {
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    pub kind: ActionKind,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomAction {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    // Model id or alias the action always uses instead of the selected model
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(flatten)]
+    pub backend: CustomActionBackend,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CustomActionBackend {
+    // Sent to the model with this system prompt
+    Prompt { system_prompt: String },
+    // Piped through a local executable instead of a model
+    Command(CommandSpec),
+}
+
+enum ActionBackend {
+    Prompt(String),
+    Command(CommandSpec),
+    Transform(&'static Transform),
+}
+
+pub fn set_custom_actions(custom_actions: Vec<CustomAction>) {
+    if let Ok(mut current) = CUSTOM_ACTIONS.write() {
+        *current = custom_actions;
+    }
+}
+
+pub fn custom_actions() -> Vec<CustomAction> {
+    CUSTOM_ACTIONS.read().map(|actions| actions.clone()).unwrap_or_default()
+}
+
+pub fn validate_custom_actions(custom_actions: &[CustomAction]) -> Result<(), String> {
+    for (index, action) in custom_actions.iter().enumerate() {
+        if action.id.is_empty() {
+            return Err(format!("Custom action {} has no id", index + 1));
+        }
+        if BUILT_IN_ACTIONS.iter().any(|(id, _, _)| *id == action.id)
+            || transforms::find(&action.id).is_some()
+            || custom_actions[..index].iter().any(|other| other.id == action.id)
+        {
+            return Err(format!("Duplicate action id: {}", action.id));
+        }
+        match &action.backend {
+            CustomActionBackend::Prompt { system_prompt } if system_prompt.trim().is_empty() => {
+                return Err(format!("Custom action '{}' has no system prompt", action.name));
+            }
+            CustomActionBackend::Command(spec) if spec.program.trim().is_empty() => {
+                return Err(format!("Custom action '{}' has no command", action.name));
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+// Built-in actions and local transforms followed by the custom ones
+pub fn available_actions() -> Vec<ActionInfo> {
+    let built_in = BUILT_IN_ACTIONS.iter().map(|(id, title, description)| ActionInfo {
+        id: id.to_string(),
+        title: title.to_string(),
+        description: description.to_string(),
+        kind: ActionKind::Ai,
+    });
+    let local = transforms::TRANSFORMS.iter().map(|transform| ActionInfo {
+        id: transform.id.to_string(),
+        title: transform.title.to_string(),
+        description: transform.description.to_string(),
+        kind: ActionKind::Local,
+    });
+    let custom = custom_actions().into_iter().map(|action| ActionInfo {
+        kind: match action.backend {
+            CustomActionBackend::Prompt { .. } => ActionKind::Ai,
+            CustomActionBackend::Command(_) => ActionKind::Command,
+        },
+        id: action.id,
+        title: action.name,
+        description: action.description,
+    });
+    built_in.chain(local).chain(custom).collect()
+}
+
+pub fn is_known_action(action: &str) -> bool {
+    resolve(action, None).is_ok()
+}
+
+pub struct ActionRequest {
+    pub text: String,
+    pub action: String,
+    pub model: String,
+    pub api_key: String,
+    pub tone: Option<String>,
+    pub max_tokens: Option<u32>,
+    // Base64 data URL of an image to send alongside the text
+    pub image: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ActionOutput {
+    pub text: String,
+    // Tokens and cost of the model call, None for command and local actions
+    pub usage: Option<Usage>,
+    // Set when the spend is past a budget's warning threshold
+    pub budget_warning: Option<String>,
+    // The model's reasoning, when it returns it separately from the answer
+    pub reasoning: Option<String>,
+}
+
+impl ActionOutput {
+    fn local(text: String) -> Self {
+        Self {
+            text,
+            usage: None,
+            budget_warning: None,
+            reasoning: None,
+        }
+    }
+}
+
+fn resolve(action: &str, tone: Option<&str>) -> Result<ActionBackend, String> {
+    if let Some(prompt) = built_in_prompt(action, tone) {
+        return Ok(ActionBackend::Prompt(prompt));
+    }
+    if let Some(transform) = transforms::find(action) {
+        return Ok(ActionBackend::Transform(transform));
+    }
+    let custom_action = custom_actions()
+        .into_iter()
+        .find(|custom_action| custom_action.id == action)
+        .ok_or_else(|| "Unknown action specified".to_string())?;
+    Ok(match custom_action.backend {
+        CustomActionBackend::Prompt { system_prompt } => ActionBackend::Prompt(system_prompt),
+        CustomActionBackend::Command(spec) => ActionBackend::Command(spec),
+    })
+}
+
+// System prompt sent for the action, or None if it doesn't call a model
+pub fn system_prompt(action: &str, tone: Option<&str>) -> Result<Option<String>, String> {
+    Ok(match resolve(action, tone)? {
+        ActionBackend::Prompt(system_prompt) => Some(system_prompt),
+        ActionBackend::Command(_) | ActionBackend::Transform(_) => None,
+    })
+}
+
+fn built_in_prompt(action: &str, tone: Option<&str>) -> Option<String> {
+    match action {
+        "proofread" => Some("You are a professional editor. Please proofread and correct the following text for grammar, spelling, punctuation, and clarity. Return only the corrected text without additional commentary.".to_string()),
+        "tone" => {
+            let tone_desc = tone.unwrap_or(DEFAULT_TONE);
+            Some(format!("You are a writing assistant. Please rewrite the following text in a {} tone. Maintain the original meaning but adjust the style and language to match the requested tone. Return only the rewritten text without additional commentary.", tone_desc))
+        },
+        "draft" => Some("You are a helpful writing assistant. Please help improve and expand the following text to make it more complete, clear, and professional. Return only the improved text without additional commentary.".to_string()),
+        _ => None
+    }
+}
+
+// Model an action runs with: its own model if it is a custom action that sets one,
+// otherwise the requested model, with aliases resolved
+pub fn effective_model(action: &str, model: &str) -> String {
+    let custom_model = custom_actions()
+        .into_iter()
+        .find(|custom_action| custom_action.id == action)
+        .and_then(|custom_action| custom_action.model)
+        .filter(|model| !model.trim().is_empty());
+    models::resolve(custom_model.as_deref().unwrap_or(model))
+}
+
+// Run the action, wrapped in the pre- and post-processing scripts attached to it
+pub async fn run_action(mut request: ActionRequest) -> Result<ActionOutput, CallError> {
+    request.model = effective_model(&request.action, &request.model);
+    let input = request.text.clone();
+    let action = request.action.clone();
+    let model = request.model.clone();
+    let tone = request.tone.clone();
+    let context = ScriptContext {
+        action: &action,
+        model: &model,
+        tone: tone.as_deref(),
+        max_tokens: request.max_tokens,
+        input: &input,
+    };
+
+    request.text = scripting::apply(Stage::Pre, request.text, &context)?;
+    let mut output = run_backend(request).await?;
+    output.text = scripting::apply(Stage::Post, output.text, &context)?;
+    Ok(output)
+}
+
+async fn run_backend(request: ActionRequest) -> Result<ActionOutput, CallError> {
+    let system_prompt = match resolve(&request.action, request.tone.as_deref())? {
+        ActionBackend::Prompt(system_prompt) => system_prompt,
+        ActionBackend::Command(spec) => {
+            if request.image.is_some() {
+                return Err(CallError::InvalidRequest("Command actions do not accept images".to_string()));
+            }
+            return Ok(ActionOutput::local(external_command::run(&spec, &request.text).await?));
+        }
+        ActionBackend::Transform(transform) => {
+            if request.image.is_some() {
+                return Err(CallError::InvalidRequest("Local actions do not accept images".to_string()));
+            }
+            return Ok(ActionOutput::local((transform.apply)(&request.text)?));
+        }
+    };
+
+    // Command and local actions work without these, so they are only checked for model calls
+    if request.api_key.is_empty() {
+        return Err(CallError::MissingApiKey);
+    }
+    if request.model.is_empty() {
+        return Err(CallError::InvalidRequest("No model selected".to_string()));
+    }
+
+    // Models missing from the catalog are sent the parameters unchecked
+    let model_info = match models::lookup(&request.api_key, &request.model).await {
+        Ok(model_info) => model_info,
+        // Images are only sent to models the catalog says accept them
+        Err(e) if request.image.is_some() => return Err(e),
+        Err(_) => None,
+    };
+    if request.image.is_some() && !model_info.as_ref().is_some_and(|model| model.accepts_input("image")) {
+        return Err(CallError::InvalidRequest(format!(
+            "Model {} does not accept image input",
+            request.model
+        )));
+    }
+    let sampling = sampling::parameters_for(&request.action, model_info.as_ref())?;
+
+    // Long texts are rewritten in parts so that each reply fits in max_tokens
+    let mut output = match split_for(&request) {
+        Some(chunks) => complete_chunks(&request, &system_prompt, &sampling, model_info.as_ref(), chunks).await?,
+        None => complete(&request, &system_prompt, user_content(&request), &sampling, model_info.as_ref()).await?,
+    };
+    // Once for the whole action, as the parts are checked against the limits one by one
+    output.budget_warning = budget::warning();
+    Ok(output)
+}
+
+// The parts of a text too long to rewrite in one call, None if it fits
+fn split_for(request: &ActionRequest) -> Option<Chunks> {
+    if !chunking::applies(&request.action) || request.image.is_some() {
+        return None;
+    }
+    let answer_tokens = request.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS);
+    let chunks = chunking::split(&request.text, chunking::chunk_limit(answer_tokens));
+    (chunks.chunks.len() > 1).then_some(chunks)
+}
+
+fn user_content(request: &ActionRequest) -> serde_json::Value {
+    match &request.image {
+        Some(data_url) => serde_json::json!([
+            {
+                "type": "text",
+                "text": request.text
+            },
+            {
+                "type": "image_url",
+                "image_url": { "url": data_url }
+            }
+        ]),
+        None => serde_json::Value::String(request.text.clone()),
+    }
+}
+
+// Send the chunks in parallel, a few at a time, and join the results in their original order
+async fn complete_chunks(
+    request: &ActionRequest,
+    system_prompt: &str,
+    sampling: &serde_json::Map<String, serde_json::Value>,
+    model_info: Option<&Model>,
+    chunks: Chunks,
+) -> Result<ActionOutput, CallError> {
+    let count = chunks.chunks.len();
+    // Owned texts, as a stream of borrowed items can't be used in the Send futures of the callers
+    let texts: Vec<String> = chunks.chunks.iter().map(|chunk| chunk.text.clone()).collect();
+    let outputs: Vec<ActionOutput> = stream::iter(texts.into_iter().enumerate())
+        .map(|(index, text)| async move {
+            complete(request, system_prompt, serde_json::Value::String(text), sampling, model_info)
+                .await
+                .map_err(|e| e.context(format!("Part {} of {}", index + 1, count)))
+        })
+        .buffered(chunking::MAX_CONCURRENT_CHUNKS)
+        .try_collect()
+        .await?;
+
+    let usages: Vec<&Usage> = outputs.iter().filter_map(|output| output.usage.as_ref()).collect();
+    let usage = (!usages.is_empty()).then(|| Usage {
+        prompt_tokens: usages.iter().map(|usage| usage.prompt_tokens).sum(),
+        completion_tokens: usages.iter().map(|usage| usage.completion_tokens).sum(),
+        // Unknown if the cost of any part is
+        cost: if usages.len() == outputs.len() {
+            usages.iter().map(|usage| usage.cost).sum()
+        } else {
+            None
+        },
+    });
+    let reasoning: Vec<&str> = outputs.iter().filter_map(|output| output.reasoning.as_deref()).collect();
+    let reasoning = (!reasoning.is_empty()).then(|| reasoning.join("\n\n"));
+
+    // The separators between the parts are put back from the original text, so only the
+    // whitespace around each answer is dropped
+    let texts = outputs.into_iter().map(|output| output.text.trim().to_string()).collect();
+    Ok(ActionOutput {
+        text: chunks.join(texts),
+        usage,
+        budget_warning: None,
+        reasoning,
+    })
+}
+
+// A single chat completion with the action's system prompt
+async fn complete(
+    request: &ActionRequest,
+    system_prompt: &str,
+    user_content: serde_json::Value,
+    sampling: &serde_json::Map<String, serde_json::Value>,
+    model_info: Option<&Model>,
+) -> Result<ActionOutput, CallError> {
+    let answer_tokens = request.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS);
+    let max_tokens = sampling::max_tokens_for(&request.action, answer_tokens);
+    let mut request_body = serde_json::json!({
+        "model": request.model,
+        "messages": [
+            {
+                "role": "system",
+                "content": system_prompt
+            },
+            {
+                "role": "user",
+                "content": user_content
+            }
+        ],
+        "max_tokens": max_tokens
+    });
+    if let Some(body) = request_body.as_object_mut() {
+        body.extend(sampling.clone());
+    }
+    routing::apply(&mut request_body);
+    usage::request_cost(&mut request_body);
+
+    // Held until the call is in the ledger, so parallel calls can't overshoot a limit together
+    let prompt_tokens = estimate::message_tokens(&request.model, &request_body["messages"]);
+    let _reservation = budget::reserve(
+        model_info.and_then(|model| model.pricing),
+        prompt_tokens,
+        u64::from(max_tokens),
+    )?;
+    let result = openrouter::chat_completion(&request.api_key, &request_body).await?;
+
+    // Routers such as openrouter/auto report the model that actually answered
+    let model = result["model"].as_str().unwrap_or(&request.model);
+    let usage = usage::from_completion(&request.api_key, model, &result).await;
+    if let Some(usage) = &usage {
+        usage::record(model, &request.action, usage);
+    }
+
+    // Reasoning models return their thinking apart from the answer, unless it is excluded
+    let choice = &result["choices"][0];
+    let reasoning = choice["message"]["reasoning"]
+        .as_str()
+        .map(str::trim)
+        .filter(|reasoning| !reasoning.is_empty())
+        .map(str::to_string);
+    let content = choice["message"]["content"].as_str();
+    // Reasoning counts against max_tokens and can use it up before the answer starts
+    if content.is_none_or(|content| content.trim().is_empty()) && choice["finish_reason"].as_str() == Some("length") {
+        return Err(CallError::Other(format!(
+            "The model used all {} tokens before it answered. Raise max tokens or lower the reasoning effort.",
+            max_tokens
+        )));
+    }
+    match content {
+        Some(content) => Ok(ActionOutput {
+            text: content.to_string(),
+            usage,
+            budget_warning: None,
+            reasoning,
+        }),
+        None if reasoning.is_some() => Err("The model returned its reasoning but no answer".into()),
+        None => Err("No content in AI response".into()),
+    }
+}
@@ -0,0 +1,369 @@
+// Daily and monthly spending limits checked before every model call. The global
+// budget covers all profiles and the CLI; a profile budget only that profile's spend.
+use crate::cli;
+use crate::error::CallError;
+use crate::models;
+use crate::openrouter::Pricing;
+use crate::usage::{self, Period, UsageRecord};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::sync::{Mutex, RwLock};
+
+const GLOBAL_BUDGET_FILE: &str = "budget.json";
+pub const DEFAULT_WARN_THRESHOLD: f64 = 0.8;
+
+// Budget stored in the running profile's settings
+static PROFILE_BUDGET: RwLock<Option<BudgetSettings>> = RwLock::new(None);
+// USD held by reservations of calls that are still running in this process
+static RESERVED: Mutex<f64> = Mutex::new(0.0);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BudgetSettings {
+    // USD, no limit when unset
+    #[serde(default)]
+    pub daily_limit: Option<f64>,
+    #[serde(default)]
+    pub monthly_limit: Option<f64>,
+    // Fraction of a limit at which results carry a warning
+    #[serde(default = "default_warn_threshold")]
+    pub warn_threshold: f64,
+}
+
+fn default_warn_threshold() -> f64 {
+    DEFAULT_WARN_THRESHOLD
+}
+
+impl Default for BudgetSettings {
+    fn default() -> Self {
+        Self {
+            daily_limit: None,
+            monthly_limit: None,
+            warn_threshold: DEFAULT_WARN_THRESHOLD,
+        }
+    }
+}
+
+impl BudgetSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        for limit in [self.daily_limit, self.monthly_limit].into_iter().flatten() {
+            if !limit.is_finite() || limit < 0.0 {
+                return Err("Budget limits must be positive amounts".to_string());
+            }
+        }
+        if !(0.0..=1.0).contains(&self.warn_threshold) {
+            return Err("The warning threshold must be between 0 and 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetScope {
+    Global,
+    Profile,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BudgetStatus {
+    pub scope: BudgetScope,
+    pub period: Period,
+    pub limit: f64,
+    pub spent: f64,
+    pub remaining: f64,
+    pub warning: bool,
+    pub exceeded: bool,
+    // Calls without a known cost, included in `spent` at the catalog's highest prices
+    pub unpriced_requests: u64,
+    // False while the model list isn't available and the unpriced calls count as free
+    pub unpriced_counted: bool,
+}
+
+impl BudgetStatus {
+    pub fn describe(&self) -> String {
+        let scope = match self.scope {
+            BudgetScope::Global => "global",
+            BudgetScope::Profile => "profile",
+        };
+        let period = match self.period {
+            Period::Day => "daily",
+            Period::Week => "weekly",
+            Period::Month => "monthly",
+        };
+        format!("{} {} budget of ${:.2} (${:.2} spent)", scope, period, self.limit, self.spent)
+    }
+}
+
+pub fn set_profile_budget(budget: Option<BudgetSettings>) {
+    if let Ok(mut current) = PROFILE_BUDGET.write() {
+        *current = budget;
+    }
+}
+
+pub fn profile_budget() -> Option<BudgetSettings> {
+    PROFILE_BUDGET.read().ok().and_then(|budget| budget.clone())
+}
+
+// Read from disk on every check so limits changed by another process apply immediately
+pub fn global_budget() -> Result<Option<BudgetSettings>, String> {
+    let path = cli::app_data_path(GLOBAL_BUDGET_FILE).ok_or("Failed to locate the app data directory")?;
+    match std::fs::read_to_string(&path) {
+        Ok(contents) => serde_json::from_str(&contents)
+            .map(Some)
+            .map_err(|e| format!("Invalid budget in {}: {}", path.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    }
+}
+
+pub fn set_global_budget(budget: &BudgetSettings) -> Result<(), String> {
+    budget.validate()?;
+    let path = cli::app_data_path(GLOBAL_BUDGET_FILE).ok_or("Failed to locate the app data directory")?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let contents = serde_json::to_string_pretty(budget).map_err(|e| e.to_string())?;
+    std::fs::write(&path, contents).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+// Spend against every configured limit in the current day and month
+pub fn status() -> Result<Vec<BudgetStatus>, String> {
+    let reserved = *RESERVED.lock().map_err(|e| e.to_string())?;
+    current_status(reserved)
+}
+
+fn current_status(reserved: f64) -> Result<Vec<BudgetStatus>, String> {
+    let budgets = [
+        (BudgetScope::Global, global_budget()?),
+        (BudgetScope::Profile, profile_budget()),
+    ];
+    if budgets.iter().all(|(_, budget)| budget.is_none()) {
+        return Ok(Vec::new());
+    }
+    let now = Local::now();
+    // The month always includes the day
+    let records = usage::records_since(usage::period_start(Period::Month, now))?;
+    let worst_case = if records.iter().any(|record| record.usage.cost.is_none()) {
+        models::highest_pricing()
+    } else {
+        None
+    };
+    Ok(compute_status(&Spending {
+        budgets: &budgets,
+        records: &records,
+        worst_case,
+        reserved,
+        profile: usage::current_profile(),
+        now,
+    }))
+}
+
+// Everything the limits are checked against, gathered apart from the checks so they can be tested
+struct Spending<'a> {
+    budgets: &'a [(BudgetScope, Option<BudgetSettings>)],
+    records: &'a [UsageRecord],
+    // Highest catalog prices, None while the model list isn't available
+    worst_case: Option<Pricing>,
+    // Highest possible cost of calls still running
+    reserved: f64,
+    profile: Option<String>,
+    now: DateTime<Local>,
+}
+
+fn compute_status(spending: &Spending) -> Vec<BudgetStatus> {
+    // An unpriced call could have cost anything, so it counts as if it used the most expensive model
+    let cost = |record: &UsageRecord| {
+        record.usage.cost.unwrap_or_else(|| {
+            spending
+                .worst_case
+                .map(|pricing| pricing.cost(record.usage.prompt_tokens, record.usage.completion_tokens))
+                .unwrap_or_default()
+        })
+    };
+    let mut statuses = Vec::new();
+    for (scope, budget) in spending.budgets {
+        let Some(budget) = budget else { continue };
+        for (period, limit) in [(Period::Day, budget.daily_limit), (Period::Month, budget.monthly_limit)] {
+            let Some(limit) = limit else { continue };
+            let since = usage::period_start(period, spending.now);
+            let counted: Vec<&UsageRecord> = spending
+                .records
+                .iter()
+                .filter(|record| record.timestamp >= since)
+                .filter(|record| *scope == BudgetScope::Global || record.profile == spending.profile)
+                .collect();
+            let spent: f64 = counted.iter().map(|record| cost(record)).sum();
+            let committed = spent + spending.reserved;
+            let unpriced_requests = counted.iter().filter(|record| record.usage.cost.is_none()).count() as u64;
+            statuses.push(BudgetStatus {
+                scope: *scope,
+                period,
+                limit,
+                spent,
+                remaining: (limit - committed).max(0.0),
+                warning: committed >= limit * budget.warn_threshold,
+                exceeded: committed >= limit,
+                unpriced_requests,
+                unpriced_counted: unpriced_requests == 0 || spending.worst_case.is_some(),
+            });
+        }
+    }
+    statuses
+}
+
+// Refuse a model call once any limit has been reached, or else hold its highest possible cost
+// against the limits until it is recorded. Calls made at the same time then see each other's
+// cost and can't all pass a check and overshoot a limit together.
+pub fn reserve(pricing: Option<Pricing>, prompt_tokens: u64, max_tokens: u64) -> Result<Reservation, CallError> {
+    let mut reserved = RESERVED.lock().map_err(|e| e.to_string())?;
+    if let Some(status) = current_status(*reserved)?.into_iter().find(|status| status.exceeded) {
+        return Err(CallError::SpendingLimit(status.describe()));
+    }
+    let amount = pricing
+        .or_else(models::highest_pricing)
+        .map(|pricing| pricing.cost(prompt_tokens, max_tokens).max(0.0))
+        .unwrap_or_default();
+    *reserved += amount;
+    Ok(Reservation { amount })
+}
+
+// Released when the call is done, by which time its actual cost is in the ledger
+pub struct Reservation {
+    amount: f64,
+}
+
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        if let Ok(mut reserved) = RESERVED.lock() {
+            *reserved = (*reserved - self.amount).max(0.0);
+        }
+    }
+}
+
+// Warning for the limit closest to being reached, if any is past its threshold
+pub fn warning() -> Option<String> {
+    let statuses = status().ok()?;
+    let status = statuses
+        .iter()
+        .filter(|status| status.warning)
+        .max_by(|a, b| (a.spent / a.limit).total_cmp(&(b.spent / b.limit)))?;
+    Some(format!("Approaching the {}, ${:.2} left", status.describe(), status.remaining))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::usage::Usage;
+    use chrono::TimeZone;
+
+    fn now() -> DateTime<Local> {
+        Local.with_ymd_and_hms(2025, 6, 12, 15, 0, 0).earliest().unwrap()
+    }
+
+    fn record(timestamp: i64, profile: Option<&str>, cost: Option<f64>) -> UsageRecord {
+        UsageRecord {
+            timestamp,
+            profile: profile.map(str::to_string),
+            model: "a/x".to_string(),
+            action: "proofread".to_string(),
+            usage: Usage {
+                prompt_tokens: 1000,
+                completion_tokens: 1000,
+                cost,
+            },
+        }
+    }
+
+    fn limits(daily_limit: Option<f64>, monthly_limit: Option<f64>) -> Option<BudgetSettings> {
+        Some(BudgetSettings {
+            daily_limit,
+            monthly_limit,
+            ..BudgetSettings::default()
+        })
+    }
+
+    fn statuses(
+        budgets: &[(BudgetScope, Option<BudgetSettings>)],
+        records: &[UsageRecord],
+        worst_case: Option<Pricing>,
+        reserved: f64,
+    ) -> Vec<BudgetStatus> {
+        compute_status(&Spending {
+            budgets,
+            records,
+            worst_case,
+            reserved,
+            profile: Some("work".to_string()),
+            now: now(),
+        })
+    }
+
+    #[test]
+    fn limits_count_their_own_period() {
+        let today = usage::period_start(Period::Day, now());
+        let records = [record(today + 60, None, Some(1.0)), record(today - 60, None, Some(2.0))];
+        let result = statuses(&[(BudgetScope::Global, limits(Some(5.0), Some(10.0)))], &records, None, 0.0);
+        assert_eq!(result.len(), 2);
+        assert_eq!((result[0].period, result[0].spent, result[0].remaining), (Period::Day, 1.0, 4.0));
+        assert_eq!((result[1].period, result[1].spent, result[1].remaining), (Period::Month, 3.0, 7.0));
+        assert!(result.iter().all(|status| !status.warning && !status.exceeded));
+    }
+
+    #[test]
+    fn profile_budget_counts_only_the_profile() {
+        let today = usage::period_start(Period::Day, now());
+        let records = [
+            record(today, Some("work"), Some(1.0)),
+            record(today, Some("home"), Some(2.0)),
+            record(today, None, Some(4.0)),
+        ];
+        let budgets = [
+            (BudgetScope::Global, limits(Some(10.0), None)),
+            (BudgetScope::Profile, limits(Some(10.0), None)),
+        ];
+        let result = statuses(&budgets, &records, None, 0.0);
+        assert_eq!(result[0].spent, 7.0);
+        assert_eq!(result[1].spent, 1.0);
+    }
+
+    #[test]
+    fn unpriced_calls_count_at_the_highest_prices() {
+        let today = usage::period_start(Period::Day, now());
+        let records = [record(today, None, Some(1.0)), record(today, None, None)];
+        let budgets = [(BudgetScope::Global, limits(Some(10.0), None))];
+        let highest = Pricing {
+            prompt: 0.001,
+            completion: 0.002,
+            request: 0.5,
+        };
+
+        let result = statuses(&budgets, &records, Some(highest), 0.0);
+        assert_eq!(result[0].unpriced_requests, 1);
+        assert!(result[0].unpriced_counted);
+        assert!((result[0].spent - 4.5).abs() < 1e-9);
+
+        // Without a model list they can't be priced, but they don't block calls either
+        let result = statuses(&budgets, &records, None, 0.0);
+        assert_eq!(result[0].unpriced_requests, 1);
+        assert!(!result[0].unpriced_counted);
+        assert_eq!(result[0].spent, 1.0);
+        assert!(!result[0].exceeded);
+    }
+
+    #[test]
+    fn reserved_cost_counts_against_the_limits() {
+        let today = usage::period_start(Period::Day, now());
+        let records = [record(today, None, Some(7.0))];
+        let budgets = [(BudgetScope::Global, limits(Some(10.0), None))];
+
+        let result = statuses(&budgets, &records, None, 2.0);
+        assert!(result[0].warning && !result[0].exceeded);
+        assert_eq!(result[0].remaining, 1.0);
+
+        let result = statuses(&budgets, &records, None, 3.0);
+        assert!(result[0].exceeded);
+        assert_eq!(result[0].remaining, 0.0);
+        // Only recorded calls are reported as spent
+        assert_eq!(result[0].spent, 7.0);
+    }
+}
@@ -0,0 +1,259 @@
+// Splitting long texts for actions that rewrite text piece by piece, so each model call stays
+// within the output limit. Chunks end at paragraph breaks where possible, then at sentence ends,
+// and the whitespace between them is kept to put the results back together.
+use std::ops::Range;
+use tiktoken_rs::CoreBPE;
+
+// Actions whose output follows the input closely enough to be processed in parts
+const CHUNKED_ACTIONS: &[&str] = &["proofread", "tone"];
+pub const MAX_CONCURRENT_CHUNKS: usize = 4;
+
+#[derive(Debug, Clone, Copy)]
+enum Break {
+    Paragraph,
+    Sentence,
+    // Last resort for sentences that don't fit on their own
+    Word,
+}
+
+#[derive(Debug, Clone)]
+pub struct Chunk {
+    pub text: String,
+    // Whitespace that followed the chunk in the original text
+    pub separator: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Chunks {
+    // Whitespace before the first chunk
+    pub leading: String,
+    pub chunks: Vec<Chunk>,
+}
+
+struct Unit {
+    range: Range<usize>,
+    separator: Range<usize>,
+    tokens: usize,
+}
+
+impl Chunks {
+    // The processed chunks, in order, with the original whitespace around them
+    pub fn join(&self, outputs: Vec<String>) -> String {
+        let mut text = self.leading.clone();
+        for (chunk, output) in self.chunks.iter().zip(outputs) {
+            text.push_str(&output);
+            text.push_str(&chunk.separator);
+        }
+        text
+    }
+}
+
+pub fn applies(action: &str) -> bool {
+    CHUNKED_ACTIONS.contains(&action)
+}
+
+// Rewrites can come out longer than their input, so chunks only fill three quarters of max_tokens
+pub fn chunk_limit(max_tokens: u32) -> usize {
+    (max_tokens as usize * 3 / 4).max(1)
+}
+
+// Split the text into chunks of at most `limit` tokens, as counted by cl100k. Only a single
+// word longer than the limit makes a chunk exceed it.
+pub fn split(text: &str, limit: usize) -> Chunks {
+    let start = text.len() - text.trim_start().len();
+    let end = text.trim_end().len();
+    if start >= end {
+        return Chunks {
+            leading: text.to_string(),
+            chunks: Vec::new(),
+        };
+    }
+
+    let bpe = tiktoken_rs::cl100k_base_singleton();
+    let mut units = Vec::new();
+    collect_units(
+        text,
+        start..end,
+        end..text.len(),
+        limit,
+        &[Break::Paragraph, Break::Sentence, Break::Word],
+        bpe,
+        &mut units,
+    );
+
+    // Merge neighbouring pieces back together as long as they fit
+    let mut chunks = Vec::new();
+    let mut current: Option<Unit> = None;
+    for unit in units {
+        current = Some(match current {
+            Some(chunk) if chunk.tokens + unit.tokens <= limit => Unit {
+                range: chunk.range.start..unit.range.end,
+                separator: unit.separator,
+                tokens: chunk.tokens + unit.tokens,
+            },
+            Some(chunk) => {
+                chunks.push(to_chunk(text, chunk));
+                unit
+            }
+            None => unit,
+        });
+    }
+    chunks.extend(current.map(|chunk| to_chunk(text, chunk)));
+
+    Chunks {
+        leading: text[..start].to_string(),
+        chunks,
+    }
+}
+
+fn to_chunk(text: &str, unit: Unit) -> Chunk {
+    Chunk {
+        text: text[unit.range].to_string(),
+        separator: text[unit.separator].to_string(),
+    }
+}
+
+// Break a piece that is too long at the first kind of break in `breaks`, and any part that is
+// still too long at the next kind
+fn collect_units(
+    text: &str,
+    range: Range<usize>,
+    separator: Range<usize>,
+    limit: usize,
+    breaks: &[Break],
+    bpe: &CoreBPE,
+    units: &mut Vec<Unit>,
+) {
+    let tokens = bpe.encode_ordinary(&text[range.clone()]).len();
+    let Some((kind, finer)) = breaks.split_first().filter(|_| tokens > limit) else {
+        units.push(Unit {
+            range,
+            separator,
+            tokens,
+        });
+        return;
+    };
+
+    let mut start = range.start;
+    for whitespace in whitespace_runs(&text[range.clone()]) {
+        let whitespace = range.start + whitespace.start..range.start + whitespace.end;
+        if is_break(*kind, &text[start..whitespace.start], &text[whitespace.clone()]) {
+            collect_units(text, start..whitespace.start, whitespace.clone(), limit, finer, bpe, units);
+            start = whitespace.end;
+        }
+    }
+    collect_units(text, start..range.end, separator, limit, finer, bpe, units);
+}
+
+fn is_break(kind: Break, before: &str, whitespace: &str) -> bool {
+    match kind {
+        Break::Paragraph => whitespace.matches('\n').count() >= 2,
+        Break::Sentence => before
+            .trim_end_matches(['"', '\'', ')', ']', '”', '’', '»'])
+            .ends_with(['.', '!', '?', '…']),
+        Break::Word => true,
+    }
+}
+
+// Byte ranges of the runs of whitespace in a text without leading or trailing whitespace
+fn whitespace_runs(text: &str) -> Vec<Range<usize>> {
+    let mut runs = Vec::new();
+    let mut run_start = None;
+    for (index, c) in text.char_indices() {
+        match (c.is_whitespace(), run_start) {
+            (true, None) => run_start = Some(index),
+            (false, Some(start)) => {
+                runs.push(start..index);
+                run_start = None;
+            }
+            _ => {}
+        }
+    }
+    runs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Joining the chunks unchanged must give back the original text
+    fn round_trip(text: &str, limit: usize) -> Chunks {
+        let chunks = split(text, limit);
+        let outputs = chunks.chunks.iter().map(|chunk| chunk.text.clone()).collect();
+        assert_eq!(chunks.join(outputs), text);
+        chunks
+    }
+
+    fn tokens(text: &str) -> usize {
+        tiktoken_rs::cl100k_base_singleton().encode_ordinary(text).len()
+    }
+
+    #[test]
+    fn short_text_is_a_single_chunk() {
+        let chunks = round_trip("Just one sentence.", 100);
+        assert_eq!(chunks.chunks.len(), 1);
+    }
+
+    #[test]
+    fn keeps_leading_and_trailing_whitespace() {
+        let chunks = round_trip("\n\t  First paragraph here.\n\nSecond paragraph here.  \n\n", 5);
+        assert_eq!(chunks.leading, "\n\t  ");
+        assert_eq!(chunks.chunks.last().unwrap().separator, "  \n\n");
+    }
+
+    #[test]
+    fn whitespace_only_text_has_no_chunks() {
+        let chunks = round_trip(" \n\n ", 5);
+        assert!(chunks.chunks.is_empty());
+    }
+
+    #[test]
+    fn splits_at_paragraph_gaps() {
+        let text = "The first paragraph is here.\n\n\nThe second paragraph follows.\r\n\r\nAnd a third one.";
+        let chunks = round_trip(text, 8);
+        let texts: Vec<&str> = chunks.chunks.iter().map(|chunk| chunk.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            ["The first paragraph is here.", "The second paragraph follows.", "And a third one."]
+        );
+        assert_eq!(chunks.chunks[0].separator, "\n\n\n");
+        assert_eq!(chunks.chunks[1].separator, "\r\n\r\n");
+    }
+
+    #[test]
+    fn falls_back_to_sentences() {
+        let text = "One sentence is here. Another one follows! Is there a third? \"Yes.\" The end.";
+        let chunks = round_trip(text, 6);
+        assert!(chunks.chunks.len() > 1);
+        for chunk in &chunks.chunks {
+            assert!(tokens(&chunk.text) <= 6, "chunk too long: {:?}", chunk.text);
+            assert!(chunk.text.ends_with(['.', '!', '?', '"']), "not a sentence end: {:?}", chunk.text);
+        }
+    }
+
+    #[test]
+    fn falls_back_to_words() {
+        let text = "a long run of words without any punctuation that has to be broken somewhere";
+        let chunks = round_trip(text, 4);
+        assert!(chunks.chunks.len() > 1);
+        for chunk in &chunks.chunks {
+            assert!(tokens(&chunk.text) <= 4, "chunk too long: {:?}", chunk.text);
+        }
+    }
+
+    #[test]
+    fn a_word_longer_than_the_limit_stays_whole() {
+        let word = "pneumonoultramicroscopicsilicovolcanoconiosis";
+        let text = format!("Short start. {} short end.", word);
+        let chunks = round_trip(&text, 3);
+        assert!(chunks.chunks.iter().any(|chunk| chunk.text == word));
+    }
+
+    #[test]
+    fn merges_small_pieces_up_to_the_limit() {
+        let text = "One.\n\nTwo.\n\nThree.\n\nFour.";
+        let chunks = round_trip(text, 100);
+        assert_eq!(chunks.chunks.len(), 1);
+        assert_eq!(chunks.chunks[0].text, text);
+    }
+}
@@ -0,0 +1,652 @@
+// Headless command-line interface reusing the same actions as the overlay
+use crate::actions::{self, ActionRequest};
+use crate::budget;
+use crate::native_messaging::{self, Browser};
+use crate::models::{self, ModelFilter};
+use crate::openrouter;
+use crate::pipeline::{self, Pipeline};
+use crate::routing;
+use crate::sampling;
+use crate::scripting;
+use crate::usage::{self, Period, SpendTotals};
+use clap::{Args, CommandFactory, Parser, Subcommand};
+use serde::de::DeserializeOwned;
+use std::ffi::OsString;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+
+// Must match the `identifier` in tauri.conf.json, the store lives in its app data directory
+pub(crate) const APP_IDENTIFIER: &str = "com.crushy.nsxt";
+// Used until a model is chosen in the settings
+pub(crate) const DEFAULT_MODEL: &str = "google/gemini-2.0-flash-001";
+
+pub const EXIT_OK: i32 = 0;
+pub const EXIT_API_ERROR: i32 = 1;
+pub const EXIT_USAGE: i32 = 2;
+pub const EXIT_CONFIG: i32 = 3;
+pub const EXIT_IO: i32 = 4;
+
+#[derive(Parser)]
+#[command(name = "wraiter", version, about = "AI quick actions for text")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    #[command(flatten)]
+    pub launch: LaunchArgs,
+}
+
+// Flags controlling how the desktop app starts when no subcommand is given
+#[derive(Args, Clone, Default)]
+pub struct LaunchArgs {
+    /// Start in the tray without showing the window (used by autostart)
+    #[arg(long)]
+    pub hidden: bool,
+    /// Use a separate settings profile
+    #[arg(long, global = true, env = "WRAITER_PROFILE", value_name = "NAME", value_parser = parse_profile_name)]
+    pub profile: Option<String>,
+    /// Open the overlay with this action selected
+    #[arg(long, value_name = "ID")]
+    pub action: Option<String>,
+}
+
+impl LaunchArgs {
+    pub fn parse_from<I, T>(args: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<std::ffi::OsString> + Clone,
+    {
+        match Cli::try_parse_from(args) {
+            Ok(cli) => cli.launch,
+            Err(e) => {
+                eprintln!("Ignoring invalid launch arguments: {}", e);
+                LaunchArgs::default()
+            }
+        }
+    }
+
+    pub fn from_env() -> Self {
+        Self::parse_from(std::env::args_os())
+    }
+}
+
+// Profile names become part of the settings file name
+fn parse_profile_name(name: &str) -> Result<String, String> {
+    if !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        Ok(name.to_string())
+    } else {
+        Err("profile names may only contain letters, digits, '-' and '_'".to_string())
+    }
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Correct grammar, spelling, punctuation and clarity
+    Proofread(ActionArgs),
+    /// Rewrite the text in a different tone
+    Tone {
+        /// Tone to rewrite in, e.g. casual, formal, friendly
+        #[arg(long, default_value = actions::DEFAULT_TONE)]
+        tone: String,
+        #[command(flatten)]
+        args: ActionArgs,
+    },
+    /// Improve and expand the text
+    Draft(ActionArgs),
+    /// Run any action by id, including custom actions
+    Run {
+        /// Id of the action
+        action: String,
+        /// Tone for the tone action
+        #[arg(long)]
+        tone: Option<String>,
+        #[command(flatten)]
+        args: ActionArgs,
+    },
+    /// Run a pipeline saved in the app settings
+    Pipeline {
+        /// Id of the pipeline
+        id: String,
+        #[command(flatten)]
+        args: ActionArgs,
+    },
+    /// List the models available on OpenRouter
+    Models {
+        #[command(flatten)]
+        connection: ConnectionArgs,
+        #[command(flatten)]
+        filter: ModelFilter,
+        /// Print the full model entries as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Check the API key and show its limits and the remaining credits
+    Key {
+        #[command(flatten)]
+        connection: ConnectionArgs,
+        /// Print the key status as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show token usage and spend recorded by the app and the CLI
+    Usage {
+        /// Period to summarize, starting at local midnight, Monday or the 1st
+        #[arg(long, value_enum, default_value = "month")]
+        period: Period,
+        /// Print the summary as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Serve the actions as Model Context Protocol tools over stdio
+    Mcp,
+    /// Serve the actions as code actions over the Language Server Protocol on stdio
+    Lsp,
+    /// Run as a browser native messaging host
+    NativeHost {
+        #[command(subcommand)]
+        command: Option<NativeHostCommand>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum NativeHostCommand {
+    /// Print the native messaging host manifest for a browser extension
+    Manifest {
+        #[arg(long, value_enum)]
+        browser: Browser,
+        /// Id of the browser extension allowed to talk to the host
+        #[arg(long)]
+        extension_id: String,
+        /// Write the manifest to the browser's native messaging hosts location
+        #[arg(long)]
+        install: bool,
+    },
+}
+
+#[derive(Args)]
+pub struct ActionArgs {
+    /// Read the input from this file instead of stdin
+    #[arg(value_name = "FILE")]
+    input: Option<PathBuf>,
+    /// OpenRouter model id, defaults to the model selected in the app settings
+    #[arg(long, env = "WRAITER_MODEL")]
+    model: Option<String>,
+    /// Maximum number of tokens to generate
+    #[arg(long)]
+    max_tokens: Option<u32>,
+    /// Print the model's reasoning to stderr, if it returns any
+    #[arg(long)]
+    show_reasoning: bool,
+    #[command(flatten)]
+    connection: ConnectionArgs,
+}
+
+#[derive(Args)]
+pub struct ConnectionArgs {
+    /// OpenRouter API key, defaults to the key saved in the app settings
+    #[arg(long, env = "OPENROUTER_API_KEY", hide_env_values = true)]
+    api_key: Option<String>,
+}
+
+impl ConnectionArgs {
+    fn resolve_api_key(&self) -> Result<String, String> {
+        if let Some(api_key) = self.api_key.as_ref().filter(|k| !k.is_empty()) {
+            return Ok(api_key.clone());
+        }
+        stored_api_key()
+    }
+}
+
+// API key from OPENROUTER_API_KEY or the app settings, for modes without an --api-key flag
+pub(crate) fn stored_api_key() -> Result<String, String> {
+    std::env::var("OPENROUTER_API_KEY")
+        .ok()
+        .or_else(|| stored_setting(crate::API_KEY).and_then(|value| value.as_str().map(str::to_string)))
+        .filter(|api_key| !api_key.is_empty())
+        .ok_or_else(|| "No OpenRouter API key configured. Pass --api-key, set OPENROUTER_API_KEY or save a key in the app settings.".to_string())
+}
+
+pub(crate) fn resolve_model(model: Option<String>) -> String {
+    model
+        .filter(|model| !model.is_empty())
+        .or_else(|| {
+            stored_setting(crate::DEFAULT_MODEL_KEY)
+                .and_then(|value| value.as_str().map(str::to_string))
+                .filter(|model| !model.is_empty())
+        })
+        .unwrap_or_else(|| DEFAULT_MODEL.to_string())
+}
+
+// Files shared by the app and the CLI live in the app's data directory
+pub(crate) fn app_data_path(file: &str) -> Option<PathBuf> {
+    Some(dirs::data_dir()?.join(APP_IDENTIFIER).join(file))
+}
+
+// Settings store of a profile, relative to the app data directory
+pub(crate) fn settings_file(profile: Option<&str>) -> String {
+    match profile {
+        Some(profile) => format!("settings-{}.json", profile),
+        None => "settings.json".to_string(),
+    }
+}
+
+// Read a value from the running profile's settings store without starting Tauri
+pub(crate) fn stored_setting(key: &str) -> Option<serde_json::Value> {
+    let path = app_data_path(&settings_file(usage::current_profile().as_deref()))?;
+    let contents = std::fs::read_to_string(path).ok()?;
+    let settings: serde_json::Value = serde_json::from_str(&contents).ok()?;
+    settings.get(key).cloned()
+}
+
+// Run the CLI if a subcommand was given, returning the process exit code.
+// Returns None when the app should start normally.
+pub fn run() -> Option<i32> {
+    let args: Vec<OsString> = std::env::args_os().collect();
+    // Browsers pass the extension origin and manifest path, which are always valid UTF-8
+    let lossy_args: Vec<String> = args.iter().map(|arg| arg.to_string_lossy().into_owned()).collect();
+    if native_messaging::is_browser_invocation(&lossy_args) {
+        // Browsers start the host without arguments, so only WRAITER_PROFILE selects a profile
+        match std::env::var("WRAITER_PROFILE").ok().map(|name| parse_profile_name(&name)).transpose() {
+            Ok(profile) => usage::set_profile(profile),
+            Err(e) => eprintln!("Ignoring WRAITER_PROFILE: {}", e),
+        }
+        load_settings(stored_setting);
+        return Some(block_on(run_server(native_messaging::serve())));
+    }
+
+    let cli = match Cli::try_parse_from(&args) {
+        Ok(cli) => cli,
+        // Launch flags such as those passed by autostart are handled by the app itself
+        Err(_) if !invokes_cli(&lossy_args) => return None,
+        Err(e) => {
+            attach_console();
+            let _ = e.print();
+            return Some(if e.use_stderr() { EXIT_USAGE } else { EXIT_OK });
+        }
+    };
+    let command = cli.command?;
+
+    attach_console();
+    usage::set_profile(cli.launch.profile);
+    load_settings(stored_setting);
+    Some(block_on(run_command(command)))
+}
+
+// Whether the arguments ask for the CLI rather than the app, so that an invalid command such as
+// `wraiter --model fast proofread` reports its error instead of silently starting the app
+fn invokes_cli(args: &[String]) -> bool {
+    let Some(first) = args.get(1) else { return false };
+    if !first.starts_with('-') || matches!(first.as_str(), "-h" | "--help" | "-V" | "--version") {
+        return true;
+    }
+    let cli = Cli::command();
+    args[1..].iter().any(|arg| {
+        cli.get_subcommands()
+            .any(|command| command.get_name() == arg || command.get_all_aliases().any(|alias| alias == arg))
+    })
+}
+
+// Custom actions, the scripts attached to actions, model aliases, sampling parameters, provider
+// preferences and the budget, read through `setting`. The app passes its profile's store, the CLI
+// passes the settings file.
+pub(crate) fn load_settings(setting: impl Fn(&str) -> Option<serde_json::Value>) {
+    load_setting(&setting, crate::CUSTOM_ACTIONS_KEY, "custom actions", actions::set_custom_actions);
+    load_setting(&setting, crate::ACTION_SCRIPTS_KEY, "action scripts", scripting::set_action_scripts);
+    load_setting(&setting, crate::MODEL_PREFERENCES_KEY, "model preferences", models::set_preferences);
+    load_setting(&setting, crate::SAMPLING_KEY, "sampling settings", |settings| {
+        sampling::set_sampling(Some(settings))
+    });
+    load_setting(&setting, crate::PROVIDER_PREFERENCES_KEY, "provider preferences", |preferences| {
+        routing::set_preferences(Some(preferences))
+    });
+    load_setting(&setting, crate::BUDGET_KEY, "budget", budget::set_profile_budget);
+}
+
+fn load_setting<T: DeserializeOwned>(
+    setting: &impl Fn(&str) -> Option<serde_json::Value>,
+    key: &str,
+    name: &str,
+    apply: impl FnOnce(T),
+) {
+    if let Some(value) = setting(key) {
+        match serde_json::from_value(value) {
+            Ok(value) => apply(value),
+            Err(e) => eprintln!("Ignoring invalid {} in settings: {}", name, e),
+        }
+    }
+}
+
+fn block_on(future: impl std::future::Future<Output = i32>) -> i32 {
+    match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime.block_on(future),
+        Err(e) => {
+            eprintln!("Failed to start async runtime: {}", e);
+            EXIT_API_ERROR
+        }
+    }
+}
+
+async fn run_command(command: Command) -> i32 {
+    let (action, tone, args) = match command {
+        Command::Proofread(args) => ("proofread", None, args),
+        Command::Tone { tone, args } => ("tone", Some(tone), args),
+        Command::Draft(args) => ("draft", None, args),
+        Command::Run { action, tone, args } => return run_action(action, tone, args).await,
+        Command::Pipeline { id, args } => return run_pipeline(&id, args).await,
+        Command::Models { connection, filter, json } => return list_models(&connection, &filter, json).await,
+        Command::Key { connection, json } => return show_key_status(&connection, json).await,
+        Command::Usage { period, json } => return show_usage(period, json),
+        Command::Mcp => return run_server(crate::mcp::serve()).await,
+        Command::Lsp => return run_server(crate::lsp::serve()).await,
+        Command::NativeHost { command: None } => return run_server(native_messaging::serve()).await,
+        Command::NativeHost { command: Some(NativeHostCommand::Manifest { browser, extension_id, install }) } => {
+            return native_host_manifest(browser, &extension_id, install);
+        }
+    };
+
+    let api_key = match args.connection.resolve_api_key() {
+        Ok(api_key) => api_key,
+        Err(e) => {
+            eprintln!("{}", e);
+            return EXIT_CONFIG;
+        }
+    };
+    execute_action(action.to_string(), tone, api_key, args).await
+}
+
+// Run a built-in or custom action; command-backed actions don't need an API key
+async fn run_action(action: String, tone: Option<String>, args: ActionArgs) -> i32 {
+    if !actions::is_known_action(&action) {
+        eprintln!("Unknown action: {}", action);
+        return EXIT_USAGE;
+    }
+    let api_key = args.connection.resolve_api_key().unwrap_or_default();
+    execute_action(action, tone, api_key, args).await
+}
+
+async fn execute_action(action: String, tone: Option<String>, api_key: String, args: ActionArgs) -> i32 {
+    let text = match read_input(args.input.as_ref()) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("{}", e);
+            return EXIT_IO;
+        }
+    };
+    if text.trim().is_empty() {
+        eprintln!("No input text provided");
+        return EXIT_USAGE;
+    }
+
+    let result = actions::run_action(ActionRequest {
+        text,
+        action,
+        model: resolve_model(args.model),
+        api_key,
+        tone,
+        max_tokens: args.max_tokens,
+        image: None,
+    })
+    .await;
+
+    match result {
+        Ok(output) => {
+            if let Some(warning) = &output.budget_warning {
+                eprintln!("Warning: {}", warning);
+            }
+            if let Some(reasoning) = output.reasoning.as_ref().filter(|_| args.show_reasoning) {
+                eprintln!("{}\n", reasoning);
+            }
+            write_output(&output.text)
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            EXIT_API_ERROR
+        }
+    }
+}
+
+// Run a long-lived stdio server mode until its input is closed
+async fn run_server(server: impl std::future::Future<Output = Result<(), String>>) -> i32 {
+    match server.await {
+        Ok(()) => EXIT_OK,
+        Err(e) => {
+            eprintln!("{}", e);
+            EXIT_IO
+        }
+    }
+}
+
+async fn run_pipeline(id: &str, args: ActionArgs) -> i32 {
+    let pipelines: Vec<Pipeline> = stored_setting(crate::PIPELINES_KEY)
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default();
+    let Some(pipeline) = pipelines.into_iter().find(|p| p.id == id) else {
+        eprintln!("Unknown pipeline: {}", id);
+        return EXIT_CONFIG;
+    };
+    // Steps backed by commands run without a key; model steps report a missing key themselves
+    let api_key = args.connection.resolve_api_key().unwrap_or_default();
+    let text = match read_input(args.input.as_ref()) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("{}", e);
+            return EXIT_IO;
+        }
+    };
+
+    let model = resolve_model(args.model);
+    let result = pipeline::run(&pipeline.steps, text, &model, &api_key, args.max_tokens).await;
+    match (result.output, result.error) {
+        (Some(output), _) => write_output(&output),
+        (None, error) => {
+            eprintln!("{}", error.unwrap_or_else(|| "Pipeline failed".to_string()));
+            EXIT_API_ERROR
+        }
+    }
+}
+
+fn native_host_manifest(browser: Browser, extension_id: &str, install: bool) -> i32 {
+    if install {
+        return match native_messaging::install_manifest(browser, extension_id) {
+            Ok(path) => write_output(&format!("Installed native messaging host manifest at {}", path.display())),
+            Err(e) => {
+                eprintln!("{}", e);
+                EXIT_IO
+            }
+        };
+    }
+    match native_messaging::manifest(browser, extension_id).and_then(|manifest| {
+        serde_json::to_string_pretty(&manifest).map_err(|e| e.to_string())
+    }) {
+        Ok(manifest) => write_output(&manifest),
+        Err(e) => {
+            eprintln!("{}", e);
+            EXIT_IO
+        }
+    }
+}
+
+async fn list_models(connection: &ConnectionArgs, filter: &ModelFilter, json: bool) -> i32 {
+    let api_key = match connection.resolve_api_key() {
+        Ok(api_key) => api_key,
+        Err(e) => {
+            eprintln!("{}", e);
+            return EXIT_CONFIG;
+        }
+    };
+    let models = match models::search(&api_key, filter).await {
+        Ok(models) => models,
+        Err(e) => {
+            eprintln!("{}", e);
+            return EXIT_API_ERROR;
+        }
+    };
+
+    let output = if json {
+        match serde_json::to_string_pretty(&models) {
+            Ok(output) => output,
+            Err(e) => {
+                eprintln!("Failed to serialize models: {}", e);
+                return EXIT_API_ERROR;
+            }
+        }
+    } else {
+        models
+            .iter()
+            .map(|model| model.id.as_str())
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    write_output(&output)
+}
+
+async fn show_key_status(connection: &ConnectionArgs, json: bool) -> i32 {
+    let api_key = match connection.resolve_api_key() {
+        Ok(api_key) => api_key,
+        Err(e) => {
+            eprintln!("{}", e);
+            return EXIT_CONFIG;
+        }
+    };
+    let status = match openrouter::fetch_key_status(&api_key).await {
+        Ok(status) => status,
+        Err(e) => {
+            eprintln!("{}", e);
+            return EXIT_API_ERROR;
+        }
+    };
+    if json {
+        return match serde_json::to_string_pretty(&status) {
+            Ok(output) => write_output(&output),
+            Err(e) => {
+                eprintln!("Failed to serialize key status: {}", e);
+                EXIT_API_ERROR
+            }
+        };
+    }
+    if !status.valid {
+        eprintln!("The API key is not valid: {}", status.error.unwrap_or_default());
+        return EXIT_CONFIG;
+    }
+
+    let dollars = |amount: Option<f64>| amount.map_or("unlimited".to_string(), |amount| format!("${:.4}", amount));
+    let mut lines = vec![
+        format!("Label:        {}", status.label.unwrap_or_default()),
+        format!("Free tier:    {}", if status.is_free_tier { "yes" } else { "no" }),
+        format!("Key usage:    ${:.4}", status.usage),
+        format!("Key limit:    {}", dollars(status.limit)),
+    ];
+    if status.limit.is_some() {
+        lines.push(format!("Key left:     {}", dollars(status.limit_remaining)));
+    }
+    if let Some(credits) = status.credits_remaining {
+        lines.push(format!("Credits left: ${:.4}", credits));
+    }
+    if let Some(rate_limit) = status.rate_limit {
+        lines.push(format!("Rate limit:   {} requests per {}", rate_limit.requests, rate_limit.interval));
+    }
+    write_output(&lines.join("\n"))
+}
+
+fn show_usage(period: Period, json: bool) -> i32 {
+    let summary = match usage::summary(period) {
+        Ok(summary) => summary,
+        Err(e) => {
+            eprintln!("{}", e);
+            return EXIT_IO;
+        }
+    };
+    if json {
+        return match serde_json::to_string_pretty(&summary) {
+            Ok(output) => write_output(&output),
+            Err(e) => {
+                eprintln!("Failed to serialize usage: {}", e);
+                EXIT_IO
+            }
+        };
+    }
+
+    let line = |name: &str, totals: &SpendTotals| {
+        format!(
+            "{:<48} {:>6} {:>10} {:>10} {:>10.4}",
+            name, totals.requests, totals.prompt_tokens, totals.completion_tokens, totals.cost
+        )
+    };
+    let mut lines = vec![format!("{:<48} {:>6} {:>10} {:>10} {:>10}", "", "calls", "prompt", "completion", "cost $")];
+    lines.push(line("Total", &summary.totals));
+    for (title, breakdown) in [("By model", &summary.by_model), ("By action", &summary.by_action)] {
+        lines.push(String::new());
+        lines.push(title.to_string());
+        lines.extend(breakdown.iter().map(|(name, totals)| line(&format!("  {}", name), totals)));
+    }
+    if summary.totals.unpriced_requests > 0 {
+        lines.push(String::new());
+        lines.push(format!("{} calls have no known price and are not included in the cost", summary.totals.unpriced_requests));
+    }
+    match budget::status() {
+        Ok(statuses) if !statuses.is_empty() => {
+            lines.push(String::new());
+            lines.push("Budgets".to_string());
+            for status in statuses {
+                let note = if status.exceeded {
+                    " (limit reached)"
+                } else if status.warning {
+                    " (warning)"
+                } else {
+                    ""
+                };
+                lines.push(format!("  {}: ${:.2} left{}", status.describe(), status.remaining, note));
+                if status.unpriced_requests > 0 && status.unpriced_counted {
+                    lines.push(format!(
+                        "    counting {} calls without a known price at the highest model prices",
+                        status.unpriced_requests
+                    ));
+                } else if status.unpriced_requests > 0 {
+                    lines.push(format!(
+                        "    not counting {} calls without a known price until the model list is loaded",
+                        status.unpriced_requests
+                    ));
+                }
+            }
+        }
+        Ok(_) => {}
+        Err(e) => eprintln!("{}", e),
+    }
+    write_output(&lines.join("\n"))
+}
+
+fn read_input(path: Option<&PathBuf>) -> Result<String, String> {
+    match path {
+        Some(path) => std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e)),
+        None => {
+            let mut text = String::new();
+            std::io::stdin()
+                .read_to_string(&mut text)
+                .map_err(|e| format!("Failed to read stdin: {}", e))?;
+            Ok(text)
+        }
+    }
+}
+
+fn write_output(output: &str) -> i32 {
+    let mut stdout = std::io::stdout().lock();
+    match writeln!(stdout, "{}", output).and_then(|_| stdout.flush()) {
+        Ok(_) => EXIT_OK,
+        Err(e) => {
+            eprintln!("Failed to write output: {}", e);
+            EXIT_IO
+        }
+    }
+}
+
+// Release builds use the Windows GUI subsystem, so reattach to the calling terminal
+#[cfg(windows)]
+fn attach_console() {
+    use windows_sys::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_console() {}
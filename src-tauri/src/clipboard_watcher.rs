@@ -0,0 +1,169 @@
+// Opt-in background clipboard watcher that runs actions on copied text
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+pub const DEFAULT_POLL_INTERVAL_MS: u64 = 1000;
+const MIN_POLL_INTERVAL_MS: u64 = 250;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum WatcherMode {
+    // Notify the user and let them run the action from the overlay
+    #[default]
+    Offer,
+    // Run the action immediately and put the result on the clipboard
+    Auto,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatcherRule {
+    pub name: String,
+    // Regex the copied text must match
+    #[serde(default)]
+    pub pattern: Option<String>,
+    #[serde(default)]
+    pub min_length: Option<usize>,
+    // Regex matched against the title or app name of the window the text was copied from
+    #[serde(default)]
+    pub source_pattern: Option<String>,
+    pub action: String,
+    #[serde(default)]
+    pub tone: Option<String>,
+    pub model: String,
+    #[serde(default)]
+    pub mode: WatcherMode,
+    // Compiled by `WatcherSettings::validate` so polling doesn't build them again
+    #[serde(skip)]
+    pattern_regex: Option<Regex>,
+    #[serde(skip)]
+    source_regex: Option<Regex>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatcherSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_poll_interval_ms")]
+    pub poll_interval_ms: u64,
+    #[serde(default)]
+    pub rules: Vec<WatcherRule>,
+}
+
+fn default_poll_interval_ms() -> u64 {
+    DEFAULT_POLL_INTERVAL_MS
+}
+
+impl Default for WatcherSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            poll_interval_ms: DEFAULT_POLL_INTERVAL_MS,
+            rules: Vec::new(),
+        }
+    }
+}
+
+impl WatcherSettings {
+    pub fn poll_interval(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.poll_interval_ms.max(MIN_POLL_INTERVAL_MS))
+    }
+
+    // Check every rule and compile its regexes, when the settings are saved or loaded
+    pub fn validate(&mut self) -> Result<(), String> {
+        for rule in &mut self.rules {
+            if rule.action.is_empty() {
+                return Err(format!("Rule '{}' has no action", rule.name));
+            }
+            if rule.model.is_empty() {
+                return Err(format!("Rule '{}' has no model", rule.name));
+            }
+            let compile = |pattern: &Option<String>| {
+                pattern
+                    .as_deref()
+                    .map(Regex::new)
+                    .transpose()
+                    .map_err(|e| format!("Invalid pattern in rule '{}': {}", rule.name, e))
+            };
+            rule.pattern_regex = compile(&rule.pattern)?;
+            rule.source_regex = compile(&rule.source_pattern)?;
+        }
+        Ok(())
+    }
+
+    // First rule matching the copied text and the window it was copied from
+    pub fn find_matching_rule(&self, text: &str, source: Option<&str>) -> Option<&WatcherRule> {
+        self.rules.iter().find(|rule| rule_matches(rule, text, source))
+    }
+}
+
+fn rule_matches(rule: &WatcherRule, text: &str, source: Option<&str>) -> bool {
+    if text.trim().is_empty() {
+        return false;
+    }
+    if let Some(min_length) = rule.min_length {
+        if text.chars().count() < min_length {
+            return false;
+        }
+    }
+    // A pattern that wasn't compiled never matches
+    if rule.pattern.is_some() && !rule.pattern_regex.as_ref().is_some_and(|re| re.is_match(text)) {
+        return false;
+    }
+    if rule.source_pattern.is_some() {
+        let Some(source) = source else {
+            return false;
+        };
+        if !rule.source_regex.as_ref().is_some_and(|re| re.is_match(source)) {
+            return false;
+        }
+    }
+    true
+}
+
+// Describe the foreground window as "<app name> - <title>" for source matching
+pub fn active_window_source() -> Option<String> {
+    active_win_pos_rs::get_active_window()
+        .ok()
+        .map(|window| format!("{} - {}", window.app_name, window.title))
+}
+
+pub struct ClipboardWatcherState(pub Mutex<WatcherSettings>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(pattern: &str, source_pattern: Option<&str>) -> WatcherSettings {
+        let rule = serde_json::json!({
+            "name": "urls",
+            "pattern": pattern,
+            "source_pattern": source_pattern,
+            "action": "proofread",
+            "model": "a/x",
+        });
+        WatcherSettings {
+            rules: vec![serde_json::from_value(rule).unwrap()],
+            ..WatcherSettings::default()
+        }
+    }
+
+    #[test]
+    fn rules_match_with_the_compiled_patterns() {
+        let mut watcher = settings("^https?://", Some("Firefox"));
+        // Not compiled yet, so nothing matches
+        assert!(watcher.find_matching_rule("https://example.com", Some("Firefox - Page")).is_none());
+
+        watcher.validate().unwrap();
+        assert!(watcher.find_matching_rule("https://example.com", Some("Firefox - Page")).is_some());
+        assert!(watcher.find_matching_rule("https://example.com", Some("Terminal")).is_none());
+        assert!(watcher.find_matching_rule("https://example.com", None).is_none());
+        assert!(watcher.find_matching_rule("plain text", Some("Firefox - Page")).is_none());
+    }
+
+    #[test]
+    fn invalid_patterns_are_rejected() {
+        assert!(settings("(unclosed", None).validate().unwrap_err().contains("rule 'urls'"));
+        assert!(settings("ok", Some("[")).validate().is_err());
+    }
+}
@@ -0,0 +1,77 @@
+// Linux session-bus service mirroring the overlay commands, so desktops where
+// global shortcuts are restricted (e.g. Wayland) can bind keys to D-Bus calls
+use crate::actions::{self, ActionRequest};
+use crate::cli;
+use crate::http_api::CredentialsProvider;
+use crate::models;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+pub const SERVICE_NAME: &str = "com.crushy.Wraiter";
+pub const OBJECT_PATH: &str = "/com/crushy/Wraiter";
+
+pub type ShowOverlay = Arc<dyn Fn() -> Pin<Box<dyn Future<Output = Result<(), String>> + Send>> + Send + Sync>;
+
+struct WraiterService {
+    credentials: CredentialsProvider,
+    show_overlay: ShowOverlay,
+}
+
+impl WraiterService {
+    fn api_key(&self) -> zbus::fdo::Result<String> {
+        let credentials = (self.credentials)();
+        if credentials.api_key.is_empty() {
+            return Err(zbus::fdo::Error::Failed("No OpenRouter API key configured".to_string()));
+        }
+        Ok(credentials.api_key)
+    }
+}
+
+#[zbus::interface(name = "com.crushy.Wraiter")]
+impl WraiterService {
+    async fn show_overlay(&self) -> zbus::fdo::Result<()> {
+        (self.show_overlay)().await.map_err(zbus::fdo::Error::Failed)
+    }
+
+    async fn run_action(&self, action: String, text: String) -> zbus::fdo::Result<String> {
+        let credentials = (self.credentials)();
+        actions::run_action(ActionRequest {
+            text,
+            action,
+            model: credentials.default_model.unwrap_or_else(|| cli::DEFAULT_MODEL.to_string()),
+            api_key: credentials.api_key,
+            tone: None,
+            max_tokens: None,
+            image: None,
+        })
+        .await
+        .map(|output| output.text)
+        .map_err(|e| zbus::fdo::Error::Failed(e.to_string()))
+    }
+
+    async fn list_models(&self) -> zbus::fdo::Result<Vec<String>> {
+        let api_key = self.api_key()?;
+        let models = models::models(&api_key)
+            .await
+            .map_err(|e| zbus::fdo::Error::Failed(e.to_string()))?;
+        Ok(models.into_iter().map(|model| model.id).collect())
+    }
+}
+
+// Register the service on the session bus and keep serving until the app exits
+pub async fn serve(credentials: CredentialsProvider, show_overlay: ShowOverlay) -> Result<(), String> {
+    let service = WraiterService {
+        credentials,
+        show_overlay,
+    };
+    let _connection = zbus::connection::Builder::session()
+        .and_then(|builder| builder.name(SERVICE_NAME))
+        .and_then(|builder| builder.serve_at(OBJECT_PATH, service))
+        .map_err(|e| format!("Failed to configure D-Bus service: {}", e))?
+        .build()
+        .await
+        .map_err(|e| format!("Failed to register D-Bus service {}: {}", SERVICE_NAME, e))?;
+    std::future::pending::<()>().await;
+    Ok(())
+}
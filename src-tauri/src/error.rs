@@ -0,0 +1,59 @@
+// Why a model call failed, so front ends such as the HTTP API can tell failures apart without
+// matching on the wording of the messages
+use std::fmt;
+
+#[derive(Debug, Clone)]
+pub enum CallError {
+    // OpenRouter answered with an error status
+    Provider { status: u16, message: String },
+    MissingApiKey,
+    SpendingLimit(String),
+    // The request can't be sent as given, e.g. no model or input the model doesn't support
+    InvalidRequest(String),
+    // Connection failures, unreadable responses, scripts and everything else
+    Other(String),
+}
+
+impl fmt::Display for CallError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CallError::Provider { message, .. } => f.write_str(message),
+            CallError::MissingApiKey => f.write_str("No OpenRouter API key configured"),
+            CallError::SpendingLimit(limit) => write!(f, "Spending limit reached: {}", limit),
+            CallError::InvalidRequest(message) | CallError::Other(message) => f.write_str(message),
+        }
+    }
+}
+
+impl CallError {
+    // Prefix the message with where the failure happened, keeping its kind
+    pub fn context(self, context: impl fmt::Display) -> Self {
+        match self {
+            CallError::Provider { status, message } => CallError::Provider {
+                status,
+                message: format!("{}: {}", context, message),
+            },
+            CallError::InvalidRequest(message) => CallError::InvalidRequest(format!("{}: {}", context, message)),
+            CallError::Other(message) => CallError::Other(format!("{}: {}", context, message)),
+            CallError::MissingApiKey | CallError::SpendingLimit(_) => self,
+        }
+    }
+}
+
+impl From<String> for CallError {
+    fn from(message: String) -> Self {
+        CallError::Other(message)
+    }
+}
+
+impl From<&str> for CallError {
+    fn from(message: &str) -> Self {
+        CallError::Other(message.to_string())
+    }
+}
+
+impl From<CallError> for String {
+    fn from(error: CallError) -> Self {
+        error.to_string()
+    }
+}
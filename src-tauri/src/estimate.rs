@@ -0,0 +1,144 @@
+// Token and cost estimates for a request before it is sent to the provider
+use crate::actions::{self, DEFAULT_MAX_TOKENS};
+use crate::chunking;
+use crate::models;
+use crate::sampling;
+use serde::Serialize;
+use tiktoken_rs::CoreBPE;
+
+// Tokens the chat format adds around each message and to prime the reply
+const TOKENS_PER_MESSAGE: u64 = 4;
+const REPLY_PRIMING_TOKENS: u64 = 3;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Estimate {
+    pub prompt_tokens: u64,
+    // Rewrites come back about as long as the input, capped by max_tokens
+    pub expected_completion_tokens: u64,
+    pub max_completion_tokens: u64,
+    // Tokenizer used to count, and whether it is the one the model actually uses
+    pub tokenizer: &'static str,
+    pub exact: bool,
+    // USD for the expected and the longest possible reply, None when pricing is unknown
+    pub expected_cost: Option<f64>,
+    pub max_cost: Option<f64>,
+    pub context_length: Option<u64>,
+    pub warning: Option<String>,
+}
+
+pub struct EstimateRequest<'a> {
+    pub text: &'a str,
+    pub action: &'a str,
+    pub model: &'a str,
+    pub api_key: &'a str,
+    pub tone: Option<&'a str>,
+    pub max_tokens: Option<u32>,
+}
+
+pub async fn estimate(request: EstimateRequest<'_>) -> Result<Estimate, String> {
+    let answer_tokens = request.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS);
+    // Includes the room left for reasoning, which is billed as completion tokens
+    let call_max_tokens = u64::from(sampling::max_tokens_for(request.action, answer_tokens));
+    let Some(system_prompt) = actions::system_prompt(request.action, request.tone)? else {
+        // Command and local actions don't call a model
+        return Ok(Estimate {
+            prompt_tokens: 0,
+            expected_completion_tokens: 0,
+            max_completion_tokens: 0,
+            tokenizer: "none",
+            exact: true,
+            expected_cost: Some(0.0),
+            max_cost: Some(0.0),
+            context_length: None,
+            warning: None,
+        });
+    };
+
+    let model = actions::effective_model(request.action, request.model);
+    let (tokenizer, bpe, exact) = tokenizer_for(&model);
+    let text_tokens = bpe.encode_ordinary(request.text).len() as u64;
+    let call_overhead = bpe.encode_ordinary(&system_prompt).len() as u64 + 2 * TOKENS_PER_MESSAGE + REPLY_PRIMING_TOKENS;
+    // Long texts are sent in parts, each with the system prompt and its own max_tokens
+    let calls = if chunking::applies(request.action) {
+        chunking::split(request.text, chunking::chunk_limit(answer_tokens)).chunks.len().max(1) as u64
+    } else {
+        1
+    };
+    let prompt_tokens = text_tokens + calls * call_overhead;
+    let max_completion_tokens = calls * call_max_tokens;
+    let expected_completion_tokens = text_tokens.min(max_completion_tokens);
+    // The context only has to hold one part at a time
+    let call_prompt_tokens = call_overhead + text_tokens.div_ceil(calls);
+
+    let info = if request.api_key.is_empty() {
+        None
+    } else {
+        models::find(request.api_key, &model).await
+    };
+    let pricing = info.as_ref().and_then(|info| info.pricing);
+    let context_length = info.as_ref().and_then(|info| info.context_length);
+
+    let warning = context_length.and_then(|context_length| {
+        if call_prompt_tokens > context_length {
+            Some(format!(
+                "The text is about {} tokens, more than the {} token context of {}",
+                call_prompt_tokens, context_length, model
+            ))
+        } else if call_prompt_tokens + call_max_tokens > context_length {
+            Some(format!(
+                "The text leaves less than the requested {} tokens for the reply in the {} token context of {}",
+                call_max_tokens, context_length, model
+            ))
+        } else {
+            None
+        }
+    });
+
+    Ok(Estimate {
+        prompt_tokens,
+        expected_completion_tokens,
+        max_completion_tokens,
+        tokenizer,
+        exact,
+        expected_cost: pricing.map(|pricing| pricing.cost(prompt_tokens, expected_completion_tokens)),
+        max_cost: pricing.map(|pricing| pricing.cost(prompt_tokens, max_completion_tokens)),
+        context_length,
+        warning,
+    })
+}
+
+// Prompt tokens of the messages of a chat completion request, counting only their text
+pub fn message_tokens(model: &str, messages: &serde_json::Value) -> u64 {
+    let (_, bpe, _) = tokenizer_for(model);
+    let Some(messages) = messages.as_array() else { return 0 };
+    let text_tokens: u64 = messages
+        .iter()
+        .map(|message| match &message["content"] {
+            serde_json::Value::String(text) => bpe.encode_ordinary(text).len() as u64,
+            serde_json::Value::Array(parts) => parts
+                .iter()
+                .filter_map(|part| part["text"].as_str())
+                .map(|text| bpe.encode_ordinary(text).len() as u64)
+                .sum(),
+            _ => 0,
+        })
+        .sum();
+    text_tokens + messages.len() as u64 * TOKENS_PER_MESSAGE + REPLY_PRIMING_TOKENS
+}
+
+// OpenAI models use their own encodings. Other providers don't publish tokenizers that
+// can run locally, so their counts are approximated with cl100k.
+fn tokenizer_for(model: &str) -> (&'static str, &'static CoreBPE, bool) {
+    let name = model.strip_prefix("openai/").unwrap_or(model);
+    let openai = model.starts_with("openai/");
+    let o200k = ["gpt-4o", "gpt-4.1", "gpt-4.5", "gpt-5", "o1", "o3", "o4", "chatgpt-4o", "gpt-oss"]
+        .iter()
+        .any(|prefix| name.starts_with(prefix));
+    if openai && o200k {
+        ("o200k_base", tiktoken_rs::o200k_base_singleton(), true)
+    } else if openai && (name.starts_with("gpt-4") || name.starts_with("gpt-3.5")) {
+        ("cl100k_base", tiktoken_rs::cl100k_base_singleton(), true)
+    } else {
+        ("cl100k_base", tiktoken_rs::cl100k_base_singleton(), false)
+    }
+}
@@ -0,0 +1,66 @@
+// Run text through a local executable for command-backed custom actions
+use serde::{Deserialize, Serialize};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+
+pub const DEFAULT_TIMEOUT_MS: u64 = 10_000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandSpec {
+    // Executable to run, looked up on PATH if not absolute. No shell is involved.
+    pub program: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+fn default_timeout_ms() -> u64 {
+    DEFAULT_TIMEOUT_MS
+}
+
+// Write the text to the command's stdin and return its stdout
+pub async fn run(spec: &CommandSpec, text: &str) -> Result<String, String> {
+    let mut child = tokio::process::Command::new(&spec.program)
+        .args(&spec.args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        // Make sure a timed-out command doesn't outlive the request
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Failed to start {}: {}", spec.program, e))?;
+
+    let mut stdin = child.stdin.take().ok_or("Failed to open the command's stdin")?;
+    let input = text.to_string();
+    // Feed stdin concurrently so commands that stream output can't deadlock on full pipes
+    let writer = tokio::spawn(async move {
+        let result = stdin.write_all(input.as_bytes()).await;
+        drop(stdin);
+        result
+    });
+
+    let output = tokio::time::timeout(Duration::from_millis(spec.timeout_ms), child.wait_with_output())
+        .await
+        .map_err(|_| format!("{} timed out after {} ms", spec.program, spec.timeout_ms))?
+        .map_err(|e| format!("Failed to run {}: {}", spec.program, e))?;
+
+    // A command may exit without reading all of its input; only report write errors on failure
+    let write_result = writer.await;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let mut message = format!("{} failed ({})", spec.program, output.status);
+        if !stderr.trim().is_empty() {
+            message.push_str(&format!(": {}", stderr.trim()));
+        } else if let Ok(Err(e)) = write_result {
+            message.push_str(&format!(": failed to write input: {}", e));
+        }
+        return Err(message);
+    }
+
+    let stdout = String::from_utf8(output.stdout)
+        .map_err(|_| format!("{} produced output that is not valid UTF-8", spec.program))?;
+    Ok(stdout.trim_end_matches(['\r', '\n']).to_string())
+}
@@ -0,0 +1,348 @@
+// Opt-in localhost HTTP API for triggering actions from editors, launchers and scripts.
+// Also serves an OpenAI-compatible /v1/chat/completions proxy backed by the stored key.
+use crate::actions::{self, ActionRequest};
+use crate::budget::{self, Reservation};
+use crate::cli;
+use crate::error::CallError;
+use crate::estimate;
+use crate::models;
+use crate::openrouter;
+use crate::routing;
+use crate::usage::{self, Usage};
+use axum::body::Body;
+use axum::extract::{Path, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+
+pub const DEFAULT_PORT: u16 = 4317;
+// Action name recorded in the usage ledger for proxied chat completions
+const PROXY_ACTION: &str = "chat";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpApiSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    // Bearer token every request must present
+    #[serde(default)]
+    pub token: String,
+}
+
+fn default_port() -> u16 {
+    DEFAULT_PORT
+}
+
+impl Default for HttpApiSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: DEFAULT_PORT,
+            token: String::new(),
+        }
+    }
+}
+
+pub fn generate_token() -> String {
+    uuid::Uuid::new_v4().simple().to_string()
+}
+
+// Credentials are looked up on every request so changes made in Settings apply immediately
+pub struct Credentials {
+    pub api_key: String,
+    pub default_model: Option<String>,
+}
+
+pub type CredentialsProvider = Arc<dyn Fn() -> Credentials + Send + Sync>;
+
+#[derive(Clone)]
+struct ApiState {
+    token: Arc<str>,
+    credentials: CredentialsProvider,
+}
+
+#[derive(Deserialize)]
+struct ActionBody {
+    text: String,
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    tone: Option<String>,
+    #[serde(default)]
+    max_tokens: Option<u32>,
+    #[serde(default)]
+    image: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ActionResponse {
+    result: String,
+    usage: Option<Usage>,
+    budget_warning: Option<String>,
+    reasoning: Option<String>,
+}
+
+struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self { status, message: message.into() }
+    }
+}
+
+// Answer with the HTTP status closest to why the model call failed
+impl From<CallError> for ApiError {
+    fn from(error: CallError) -> Self {
+        let status = match &error {
+            CallError::Provider { status: 401 | 403, .. } => StatusCode::UNAUTHORIZED,
+            CallError::Provider { status: 402, .. } | CallError::SpendingLimit(_) => StatusCode::PAYMENT_REQUIRED,
+            CallError::Provider { status: 429, .. } => StatusCode::TOO_MANY_REQUESTS,
+            CallError::MissingApiKey => StatusCode::SERVICE_UNAVAILABLE,
+            CallError::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+            CallError::Provider { .. } | CallError::Other(_) => StatusCode::BAD_GATEWAY,
+        };
+        Self::new(status, error.to_string())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = serde_json::json!({ "error": { "message": self.message } });
+        (self.status, Json(body)).into_response()
+    }
+}
+
+// A server whose port is already bound, so a port in use is reported before it starts serving
+pub struct Server {
+    listener: tokio::net::TcpListener,
+    router: Router,
+}
+
+impl Server {
+    // Serve the API until the returned future is dropped
+    pub async fn run(self) -> Result<(), String> {
+        axum::serve(self.listener, self.router)
+            .await
+            .map_err(|e| format!("HTTP API server failed: {}", e))
+    }
+}
+
+// Bind the API to its port on 127.0.0.1
+pub async fn bind(settings: &HttpApiSettings, credentials: CredentialsProvider) -> Result<Server, String> {
+    if settings.token.is_empty() {
+        return Err("The HTTP API requires a token".to_string());
+    }
+    let state = ApiState {
+        token: Arc::from(settings.token.as_str()),
+        credentials,
+    };
+    let router = Router::new()
+        .route("/v1/models", get(list_models))
+        .route("/v1/chat/completions", post(chat_completions))
+        .route("/v1/actions/{id}", post(run_action))
+        .route_layer(middleware::from_fn_with_state(state.clone(), require_token))
+        .with_state(state);
+
+    let address = SocketAddr::from((Ipv4Addr::LOCALHOST, settings.port));
+    let listener = tokio::net::TcpListener::bind(address)
+        .await
+        .map_err(|e| format!("Failed to bind HTTP API to {}: {}", address, e))?;
+    Ok(Server { listener, router })
+}
+
+async fn require_token(State(state): State<ApiState>, request: Request, next: Next) -> Response {
+    let authorized = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|token| constant_time_eq(token.as_bytes(), state.token.as_bytes()));
+    if !authorized {
+        return ApiError::new(StatusCode::UNAUTHORIZED, "Missing or invalid bearer token").into_response();
+    }
+    next.run(request).await
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+fn api_key(credentials: &Credentials) -> Result<String, ApiError> {
+    if credentials.api_key.is_empty() {
+        return Err(ApiError::new(StatusCode::SERVICE_UNAVAILABLE, "No OpenRouter API key configured in Wraiter"));
+    }
+    Ok(credentials.api_key.clone())
+}
+
+async fn list_models(State(state): State<ApiState>) -> Result<Json<serde_json::Value>, ApiError> {
+    let api_key = api_key(&(state.credentials)())?;
+    let models = models::models(&api_key).await?;
+    let models: Vec<serde_json::Value> = models
+        .into_iter()
+        .filter_map(|model| serde_json::to_value(model).ok())
+        .map(openai_model_entry)
+        .collect();
+    Ok(Json(serde_json::json!({ "object": "list", "data": models })))
+}
+
+// Add the fields OpenAI clients expect to an OpenRouter model entry
+fn openai_model_entry(mut model: serde_json::Value) -> serde_json::Value {
+    let owned_by = model["id"]
+        .as_str()
+        .and_then(|id| id.split('/').next())
+        .unwrap_or("openrouter")
+        .to_string();
+    if let Some(entry) = model.as_object_mut() {
+        entry.insert("object".to_string(), serde_json::Value::from("model"));
+        entry.entry("owned_by").or_insert(serde_json::Value::from(owned_by));
+    }
+    model
+}
+
+// Forward an OpenAI-style chat completion request to OpenRouter with the stored key
+async fn chat_completions(
+    State(state): State<ApiState>,
+    Json(mut body): Json<serde_json::Value>,
+) -> Result<Response, ApiError> {
+    let credentials = (state.credentials)();
+    let api_key = api_key(&credentials)?;
+    if !body.is_object() {
+        return Err(ApiError::new(StatusCode::BAD_REQUEST, "Request body must be a JSON object"));
+    }
+    if body["model"].as_str().is_none_or(str::is_empty) {
+        let model = credentials.default_model.unwrap_or_else(|| cli::DEFAULT_MODEL.to_string());
+        body["model"] = serde_json::Value::String(model);
+    }
+
+    let model = models::resolve(body["model"].as_str().unwrap_or_default());
+    body["model"] = serde_json::Value::String(model.clone());
+    routing::apply(&mut body);
+    usage::request_cost(&mut body);
+
+    // Held until the call is in the ledger, so parallel calls can't overshoot a limit together
+    let pricing = models::find(&api_key, &model).await.and_then(|info| info.pricing);
+    let max_tokens = body["max_completion_tokens"]
+        .as_u64()
+        .or_else(|| body["max_tokens"].as_u64())
+        .unwrap_or(u64::from(actions::DEFAULT_MAX_TOKENS));
+    let reservation = budget::reserve(pricing, estimate::message_tokens(&model, &body["messages"]), max_tokens)?;
+
+    if body["stream"].as_bool() == Some(true) {
+        // Ask for the final usage chunk so streamed calls are recorded too
+        if body.get("stream_options").is_none() {
+            body["stream_options"] = serde_json::json!({ "include_usage": true });
+        }
+        let upstream = openrouter::chat_completion_stream(&api_key, &body)
+            .await?;
+        let mut tracker = StreamUsageTracker::new(api_key, model, reservation);
+        let stream = upstream.bytes_stream().map(move |chunk| {
+            if let Ok(bytes) = &chunk {
+                tracker.feed(bytes);
+            }
+            chunk
+        });
+        return Ok((
+            [(header::CONTENT_TYPE, "text/event-stream"), (header::CACHE_CONTROL, "no-cache")],
+            Body::from_stream(stream),
+        )
+            .into_response());
+    }
+
+    let completion = openrouter::chat_completion(&api_key, &body)
+        .await?;
+    record_usage(&api_key, &model, &completion).await;
+    drop(reservation);
+    Ok(Json(completion).into_response())
+}
+
+async fn record_usage(api_key: &str, requested_model: &str, completion: &serde_json::Value) {
+    let model = completion["model"].as_str().unwrap_or(requested_model);
+    if let Some(usage) = usage::from_completion(api_key, model, completion).await {
+        usage::record(model, PROXY_ACTION, &usage);
+    }
+}
+
+// Watches a forwarded event stream for the chunk carrying the usage block
+struct StreamUsageTracker {
+    api_key: String,
+    model: String,
+    pending: Vec<u8>,
+    // Released once the usage is recorded, or when the stream ends without it
+    reservation: Option<Reservation>,
+}
+
+impl StreamUsageTracker {
+    fn new(api_key: String, model: String, reservation: Reservation) -> Self {
+        Self {
+            api_key,
+            model,
+            pending: Vec::new(),
+            reservation: Some(reservation),
+        }
+    }
+
+    fn feed(&mut self, bytes: &[u8]) {
+        self.pending.extend_from_slice(bytes);
+        while let Some(end) = self.pending.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=end).collect();
+            let Some(data) = std::str::from_utf8(&line).ok().and_then(|line| line.trim().strip_prefix("data:")) else {
+                continue;
+            };
+            let Ok(chunk) = serde_json::from_str::<serde_json::Value>(data.trim()) else {
+                continue;
+            };
+            if chunk["usage"].is_object() {
+                let (api_key, model) = (self.api_key.clone(), self.model.clone());
+                let reservation = self.reservation.take();
+                tokio::spawn(async move {
+                    record_usage(&api_key, &model, &chunk).await;
+                    drop(reservation);
+                });
+            }
+        }
+    }
+}
+
+async fn run_action(
+    State(state): State<ApiState>,
+    Path(id): Path<String>,
+    Json(body): Json<ActionBody>,
+) -> Result<Json<ActionResponse>, ApiError> {
+    let credentials = (state.credentials)();
+    if !actions::is_known_action(&id) {
+        return Err(ApiError::new(StatusCode::NOT_FOUND, format!("Unknown action: {}", id)));
+    }
+    let model = body
+        .model
+        .filter(|model| !model.is_empty())
+        .or(credentials.default_model)
+        .unwrap_or_else(|| cli::DEFAULT_MODEL.to_string());
+
+    let output = actions::run_action(ActionRequest {
+        text: body.text,
+        action: id,
+        model,
+        api_key: credentials.api_key,
+        tone: body.tone,
+        max_tokens: body.max_tokens,
+        image: body.image,
+    })
+    .await?;
+
+    Ok(Json(ActionResponse {
+        result: output.text,
+        usage: output.usage,
+        budget_warning: output.budget_warning,
+        reasoning: output.reasoning,
+    }))
+}
@@ -1,34 +1,122 @@
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
-use tauri::Manager;
+mod actions;
+mod budget;
+mod chunking;
+mod clipboard_watcher;
+pub mod cli;
+#[cfg(target_os = "linux")]
+mod dbus;
+mod error;
+mod estimate;
+mod external_command;
+mod http_api;
+mod lsp;
+mod mcp;
+mod models;
+mod native_messaging;
+mod openrouter;
+mod pipeline;
+mod routing;
+mod sampling;
+mod scripting;
+mod transforms;
+mod usage;
+
+use actions::{ActionInfo, ActionOutput, ActionRequest, CustomAction};
+use budget::{BudgetScope, BudgetSettings, BudgetStatus};
+use estimate::{Estimate, EstimateRequest};
+use models::{ModelFilter, ModelPreferences};
+use cli::LaunchArgs;
+use http_api::{Credentials, CredentialsProvider, HttpApiSettings};
+use pipeline::{Pipeline, PipelineResult};
+use routing::ProviderPreferences;
+use sampling::SamplingSettings;
+use scripting::ActionScript;
+use usage::{Period, UsageSummary};
+use clipboard_watcher::{ClipboardWatcherState, WatcherMode, WatcherSettings};
+use tauri::{Emitter, Manager};
 use tauri::menu::{Menu, MenuItem, CheckMenuItem};
 use tauri::tray::TrayIconBuilder;
 use tauri_plugin_autostart::{MacosLauncher, ManagerExt};
 use tauri_plugin_clipboard_manager::ClipboardExt;
 use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutEvent};
-use tauri_plugin_store::{StoreBuilder};
+use tauri_plugin_notification::NotificationExt;
+use tauri_plugin_store::{Store, StoreBuilder};
 use std::str::FromStr;
 use std::time::{Duration, Instant};
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
+use base64::prelude::{Engine as _, BASE64_STANDARD};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 
 
 const SHORTCUT_KEY: &str = "shortcut";
 const AUTO_CLOSE_KEY: &str = "auto_close";
 const DEFAULT_SHORTCUT: &str = "CommandOrControl+Shift+A";
 const API_KEY: &str = "openrouter_api_key";
+const DEFAULT_MODEL_KEY: &str = "default_model";
+const CLIPBOARD_WATCHER_KEY: &str = "clipboard_watcher";
+const HTTP_API_KEY: &str = "http_api";
+const PIPELINES_KEY: &str = "pipelines";
+const CUSTOM_ACTIONS_KEY: &str = "custom_actions";
+const ACTION_SCRIPTS_KEY: &str = "action_scripts";
+const BUDGET_KEY: &str = "budget";
+const MODEL_PREFERENCES_KEY: &str = "model_preferences";
+const SAMPLING_KEY: &str = "sampling";
+const PROVIDER_PREFERENCES_KEY: &str = "provider_preferences";
 
 // Global static for debouncing shortcut triggers
 static LAST_SHORTCUT_TRIGGER: Mutex<Option<Instant>> = Mutex::new(None);
 
-#[tauri::command]
-fn get_shortcut(app: tauri::AppHandle) -> Result<String, String> {
-    let store = StoreBuilder::new(&app, "settings.json").build().map_err(|e| e.to_string())?;
+// Launch options parsed from the command line, plus the action still waiting for the overlay
+struct LaunchState {
+    profile: Option<String>,
+    pending_action: Mutex<Option<String>>,
+}
+
+// Settings store of the profile selected with --profile
+fn settings_file(app: &tauri::AppHandle) -> String {
+    let profile = app.try_state::<LaunchState>().and_then(|launch| launch.profile.clone());
+    cli::settings_file(profile.as_deref())
+}
+
+// Reloaded on every access, since the CLI or the user may have changed the file since
+fn settings_store(app: &tauri::AppHandle) -> Result<Arc<Store<tauri::Wry>>, String> {
+    let store = StoreBuilder::new(app, settings_file(app)).build().map_err(|e| e.to_string())?;
     store.reload().map_err(|e| e.to_string())?;
-    match store.get(SHORTCUT_KEY) {
-        Some(shortcut) => Ok(shortcut.as_str().unwrap().to_string()),
-        None => Ok(DEFAULT_SHORTCUT.to_string()),
+    Ok(store)
+}
+
+// A missing key gives the default, a stored value that doesn't parse is reported
+fn load_setting<T: DeserializeOwned>(app: &tauri::AppHandle, key: &str, default: T) -> Result<T, String> {
+    match settings_store(app)?.get(key) {
+        Some(value) => serde_json::from_value(value).map_err(|e| e.to_string()),
+        None => Ok(default),
     }
 }
 
+fn save_setting<T: Serialize>(app: &tauri::AppHandle, key: &str, value: &T) -> Result<(), String> {
+    let store = settings_store(app)?;
+    store.set(key.to_string(), serde_json::to_value(value).map_err(|e| e.to_string())?);
+    store.save().map_err(|e| e.to_string())
+}
+
+// Return the action requested with --action once, so the overlay can preselect it
+#[tauri::command]
+fn take_launch_action(app: tauri::AppHandle) -> Result<Option<String>, String> {
+    let launch = app.state::<LaunchState>();
+    let mut pending_action = launch.pending_action.lock().map_err(|e| e.to_string())?;
+    Ok(pending_action.take())
+}
+
+#[tauri::command]
+fn get_shortcut(app: tauri::AppHandle) -> Result<String, String> {
+    let shortcut = settings_store(&app)?.get(SHORTCUT_KEY);
+    Ok(shortcut
+        .and_then(|shortcut| shortcut.as_str().map(str::to_string))
+        .unwrap_or_else(|| DEFAULT_SHORTCUT.to_string()))
+}
+
 #[tauri::command]
 async fn set_shortcut(app: tauri::AppHandle, shortcut: String) -> Result<(), String> {
     app.global_shortcut()
@@ -60,12 +148,7 @@ async fn set_shortcut(app: tauri::AppHandle, shortcut: String) -> Result<(), Str
         .on_shortcut(new_shortcut, show_overlay_callback)
         .map_err(|e| e.to_string())?;
 
-    let store = StoreBuilder::new(&app, "settings.json").build().map_err(|e| e.to_string())?;
-    store.reload().map_err(|e| e.to_string())?;
-    store.set(SHORTCUT_KEY.to_string(), serde_json::Value::String(shortcut));
-    store.save().map_err(|e| e.to_string())?;
-
-    Ok(())
+    save_setting(&app, SHORTCUT_KEY, &shortcut)
 }
 
 #[tauri::command]
@@ -90,42 +173,38 @@ async fn is_autostart_enabled(app: tauri::AppHandle) -> Result<bool, String> {
 
 #[tauri::command]
 fn get_auto_close(app: tauri::AppHandle) -> Result<bool, String> {
-    let store = StoreBuilder::new(&app, "settings.json").build().map_err(|e| e.to_string())?;
-    store.reload().map_err(|e| e.to_string())?;
-    match store.get(AUTO_CLOSE_KEY) {
-        Some(auto_close) => Ok(auto_close.as_bool().unwrap_or(true)),
-        None => Ok(true), // Default to true
-    }
+    let auto_close = settings_store(&app)?.get(AUTO_CLOSE_KEY);
+    Ok(auto_close.and_then(|auto_close| auto_close.as_bool()).unwrap_or(true))
 }
 
 #[tauri::command]
 async fn set_auto_close(app: tauri::AppHandle, auto_close: bool) -> Result<(), String> {
-    let store = StoreBuilder::new(&app, "settings.json").build().map_err(|e| e.to_string())?;
-    store.reload().map_err(|e| e.to_string())?;
-    store.set(AUTO_CLOSE_KEY.to_string(), serde_json::Value::Bool(auto_close));
-    store.save().map_err(|e| e.to_string())?;
-    Ok(())
+    save_setting(&app, AUTO_CLOSE_KEY, &auto_close)
 }
 
 // Get the saved OpenRouter API key from the persistent store
 #[tauri::command]
 fn get_api_key(app: tauri::AppHandle) -> Result<String, String> {
-    let store = StoreBuilder::new(&app, "settings.json").build().map_err(|e| e.to_string())?;
-    store.reload().map_err(|e| e.to_string())?;
-    match store.get(API_KEY) {
-        Some(val) => Ok(val.as_str().unwrap_or_default().to_string()),
-        None => Ok(String::new()),
-    }
+    let val = settings_store(&app)?.get(API_KEY);
+    Ok(val.and_then(|val| val.as_str().map(str::to_string)).unwrap_or_default())
 }
 
 // Save the OpenRouter API key into the persistent store
 #[tauri::command]
 async fn set_api_key(app: tauri::AppHandle, api_key: String) -> Result<(), String> {
-    let store = StoreBuilder::new(&app, "settings.json").build().map_err(|e| e.to_string())?;
-    store.reload().map_err(|e| e.to_string())?;
-    store.set(API_KEY.to_string(), serde_json::Value::String(api_key));
-    store.save().map_err(|e| e.to_string())?;
-    Ok(())
+    save_setting(&app, API_KEY, &api_key)
+}
+
+// Get the model selected in Settings, used by callers that don't pass a model
+#[tauri::command]
+fn get_default_model(app: tauri::AppHandle) -> Result<String, String> {
+    let val = settings_store(&app)?.get(DEFAULT_MODEL_KEY);
+    Ok(val.and_then(|val| val.as_str().map(str::to_string)).unwrap_or_default())
+}
+
+#[tauri::command]
+async fn set_default_model(app: tauri::AppHandle, model: String) -> Result<(), String> {
+    save_setting(&app, DEFAULT_MODEL_KEY, &model)
 }
 
 #[tauri::command]
@@ -164,6 +243,32 @@ async fn get_clipboard_text(app: tauri::AppHandle) -> Result<String, String> {
     }
 }
 
+// Read an image from the clipboard and return it as a base64 PNG data URL,
+// or None when the clipboard does not hold an image
+#[tauri::command]
+async fn get_clipboard_image(app: tauri::AppHandle) -> Result<Option<String>, String> {
+    let clipboard = app.clipboard();
+    match clipboard.read_image() {
+        Ok(image) => {
+            let data_url = encode_png_data_url(image.rgba(), image.width(), image.height())?;
+            Ok(Some(data_url))
+        }
+        Err(_) => Ok(None),
+    }
+}
+
+fn encode_png_data_url(rgba: &[u8], width: u32, height: u32) -> Result<String, String> {
+    let mut png_bytes = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut png_bytes, width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(|e| format!("Failed to encode image: {}", e))?;
+        writer.write_image_data(rgba).map_err(|e| format!("Failed to encode image: {}", e))?;
+    }
+    Ok(format!("data:image/png;base64,{}", BASE64_STANDARD.encode(&png_bytes)))
+}
+
 #[tauri::command]
 async fn set_clipboard_text(app: tauri::AppHandle, text: String) -> Result<(), String> {
     let clipboard = app.clipboard();
@@ -173,49 +278,187 @@ async fn set_clipboard_text(app: tauri::AppHandle, text: String) -> Result<(), S
     }
 }
 
+fn load_clipboard_watcher_settings(app: &tauri::AppHandle) -> Result<WatcherSettings, String> {
+    let mut settings = load_setting(app, CLIPBOARD_WATCHER_KEY, WatcherSettings::default())?;
+    settings.validate()?;
+    Ok(settings)
+}
+
 #[tauri::command]
-async fn fetch_openrouter_models(api_key: String) -> Result<Vec<serde_json::Value>, String> {
-    let client = reqwest::Client::new();
-    let response = client
-        .get("https://openrouter.ai/api/v1/models")
-        .header("Authorization", format!("Bearer {}", api_key))
-        .header("Content-Type", "application/json")
-        .send()
-        .await;
-
-    match response {
-        Ok(resp) => {
-            let status = resp.status();
-            if status.is_success() {
-                match resp.json::<serde_json::Value>().await {
-                    Ok(models) => {
-                        if let Some(models_array) = models["data"].as_array() {
-                            Ok(models_array.clone())
-                        } else {
-                            Ok(Vec::new())
-                        }
-                    }
-                    Err(e) => Err(format!("Failed to parse models response: {}", e))
+fn get_clipboard_watcher(app: tauri::AppHandle) -> Result<WatcherSettings, String> {
+    let state = app.state::<ClipboardWatcherState>();
+    let settings = state.0.lock().map_err(|e| e.to_string())?;
+    Ok(settings.clone())
+}
+
+#[tauri::command]
+async fn set_clipboard_watcher(app: tauri::AppHandle, mut settings: WatcherSettings) -> Result<(), String> {
+    settings.validate()?;
+
+    save_setting(&app, CLIPBOARD_WATCHER_KEY, &settings)?;
+
+    let state = app.state::<ClipboardWatcherState>();
+    *state.0.lock().map_err(|e| e.to_string())? = settings;
+    Ok(())
+}
+
+#[derive(Clone, serde::Serialize)]
+struct ClipboardWatcherMatch {
+    rule: String,
+    action: String,
+    tone: Option<String>,
+    model: String,
+    text: String,
+}
+
+// Poll the clipboard and run the first matching watcher rule on newly copied text
+fn spawn_clipboard_watcher(app: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut last_text: Option<String> = None;
+        loop {
+            let settings = match app.state::<ClipboardWatcherState>().0.lock() {
+                Ok(settings) => settings.clone(),
+                Err(_) => return,
+            };
+            tokio::time::sleep(settings.poll_interval()).await;
+
+            if !settings.enabled {
+                last_text = None;
+                continue;
+            }
+
+            let text = match app.clipboard().read_text() {
+                Ok(text) => text,
+                Err(_) => continue,
+            };
+            if last_text.as_deref() == Some(text.as_str()) {
+                continue;
+            }
+            // Don't act on whatever was already on the clipboard when watching started
+            let first_poll = last_text.is_none();
+            last_text = Some(text.clone());
+            if first_poll {
+                continue;
+            }
+
+            let source = clipboard_watcher::active_window_source();
+            let rule = match settings.find_matching_rule(&text, source.as_deref()) {
+                Some(rule) => rule.clone(),
+                None => continue,
+            };
+
+            match rule.mode {
+                WatcherMode::Offer => {
+                    let _ = app.emit("clipboard-watcher-match", ClipboardWatcherMatch {
+                        rule: rule.name.clone(),
+                        action: rule.action.clone(),
+                        tone: rule.tone.clone(),
+                        model: rule.model.clone(),
+                        text,
+                    });
+                    notify(&app, &rule.name, &format!("Copied text matches this rule. Open Wraiter to run {}.", rule.action));
                 }
-            } else {
-                match resp.text().await {
-                    Ok(error_text) => {
-                        if status == 401 {
-                            Err(format!("Authentication failed (401 Unauthorized): {}", error_text))
-                        } else if status == 403 {
-                            Err(format!("Access forbidden (403 Forbidden): {}", error_text))
-                        } else {
-                            Err(format!("API request failed ({}): {}", status, error_text))
+                WatcherMode::Auto => {
+                    let api_key = get_api_key(app.clone()).unwrap_or_default();
+                    match process_text_with_ai(text, rule.action.clone(), rule.model.clone(), api_key, rule.tone.clone(), None, None).await {
+                        Ok(ActionOutput { text: result, .. }) => {
+                            if let Err(e) = app.clipboard().write_text(&result) {
+                                notify(&app, &rule.name, &format!("Failed to write to clipboard: {}", e));
+                                continue;
+                            }
+                            // Don't treat our own result as a new copy
+                            last_text = Some(result);
+                            notify(&app, &rule.name, &format!("The {} result was copied to the clipboard.", rule.action));
                         }
-                    },
-                    Err(_) => Err(format!("API request failed with status: {}", status))
+                        Err(e) => notify(&app, &rule.name, &format!("Failed to run {}: {}", rule.action, e)),
+                    }
                 }
             }
         }
-        Err(e) => Err(format!("Failed to connect to OpenRouter API: {}", e))
+    });
+}
+
+fn notify(app: &tauri::AppHandle, title: &str, body: &str) {
+    if let Err(e) = app.notification().builder().title(title).body(body).show() {
+        eprintln!("Failed to show notification: {}", e);
     }
 }
 
+// Handle of the running HTTP API server, if enabled
+struct HttpApiState(Mutex<Option<tauri::async_runtime::JoinHandle<()>>>);
+
+fn load_http_api_settings(app: &tauri::AppHandle) -> Result<HttpApiSettings, String> {
+    load_setting(app, HTTP_API_KEY, HttpApiSettings::default())
+}
+
+// Read the API key and default model from the store for servers running outside the webview
+fn stored_credentials(app: &tauri::AppHandle) -> CredentialsProvider {
+    let app = app.clone();
+    Arc::new(move || Credentials {
+        api_key: get_api_key(app.clone()).unwrap_or_default(),
+        default_model: get_default_model(app.clone()).ok().filter(|model| !model.is_empty()),
+    })
+}
+
+// Stop the running HTTP API server and start a new one if it is enabled
+async fn restart_http_api(app: &tauri::AppHandle, settings: HttpApiSettings) -> Result<(), String> {
+    let previous = app.state::<HttpApiState>().0.lock().map_err(|e| e.to_string())?.take();
+    if let Some(handle) = previous {
+        handle.abort();
+        // Wait for the listener to be dropped so the port can be bound again
+        let _ = handle.await;
+    }
+    if !settings.enabled {
+        return Ok(());
+    }
+
+    let server = http_api::bind(&settings, stored_credentials(app)).await?;
+    let handle = tauri::async_runtime::spawn(async move {
+        if let Err(e) = server.run().await {
+            eprintln!("{}", e);
+        }
+    });
+    *app.state::<HttpApiState>().0.lock().map_err(|e| e.to_string())? = Some(handle);
+    Ok(())
+}
+
+#[tauri::command]
+fn get_http_api(app: tauri::AppHandle) -> Result<HttpApiSettings, String> {
+    load_http_api_settings(&app)
+}
+
+#[tauri::command]
+async fn set_http_api(app: tauri::AppHandle, mut settings: HttpApiSettings) -> Result<HttpApiSettings, String> {
+    if settings.port == 0 {
+        return Err("Invalid port".to_string());
+    }
+    if settings.token.is_empty() {
+        settings.token = http_api::generate_token();
+    }
+
+    save_setting(&app, HTTP_API_KEY, &settings)?;
+
+    restart_http_api(&app, settings.clone()).await?;
+    Ok(settings)
+}
+
+#[tauri::command]
+async fn regenerate_http_api_token(app: tauri::AppHandle) -> Result<HttpApiSettings, String> {
+    let mut settings = load_http_api_settings(&app)?;
+    settings.token = http_api::generate_token();
+    set_http_api(app, settings).await
+}
+
+#[tauri::command]
+async fn fetch_openrouter_models(api_key: String) -> Result<Vec<openrouter::Model>, String> {
+    models::models(&api_key).await.map_err(String::from)
+}
+
+#[tauri::command]
+async fn search_models(api_key: String, filter: ModelFilter) -> Result<Vec<openrouter::Model>, String> {
+    models::search(&api_key, &filter).await.map_err(String::from)
+}
+
 #[tauri::command]
 async fn process_text_with_ai(
     text: String,
@@ -223,80 +466,183 @@ async fn process_text_with_ai(
     model: String,
     api_key: String,
     tone: Option<String>,
-    max_tokens: Option<u32>
-) -> Result<String, String> {
-    let client = reqwest::Client::new();
-
-    let system_prompt = match action.as_str() {
-        "proofread" => "You are a professional editor. Please proofread and correct the following text for grammar, spelling, punctuation, and clarity. Return only the corrected text without additional commentary.".to_string(),
-        "tone" => {
-            let tone_desc = tone.unwrap_or_else(|| "professional".to_string());
-            format!("You are a writing assistant. Please rewrite the following text in a {} tone. Maintain the original meaning but adjust the style and language to match the requested tone. Return only the rewritten text without additional commentary.", tone_desc)
-        },
-        "draft" => "You are a helpful writing assistant. Please help improve and expand the following text to make it more complete, clear, and professional. Return only the improved text without additional commentary.".to_string(),
-        _ => return Err("Unknown action specified".to_string())
-    };
+    max_tokens: Option<u32>,
+    image: Option<String>
+) -> Result<ActionOutput, String> {
+    actions::run_action(ActionRequest {
+        text,
+        action,
+        model,
+        api_key,
+        tone,
+        max_tokens,
+        image,
+    })
+    .await
+    .map_err(String::from)
+}
 
-    let request_body = serde_json::json!({
-        "model": model,
-        "messages": [
-            {
-                "role": "system",
-                "content": system_prompt
-            },
-            {
-                "role": "user",
-                "content": text
-            }
-        ],
-        "max_tokens": max_tokens.unwrap_or(2000),
-        "temperature": 0.7
-    });
+// Validate the key and report its limits, rate limit and the remaining credits
+#[tauri::command]
+async fn get_key_status(api_key: String) -> Result<openrouter::KeyStatus, String> {
+    openrouter::fetch_key_status(&api_key).await
+}
 
-    let response = client
-        .post("https://openrouter.ai/api/v1/chat/completions")
-        .header("Authorization", format!("Bearer {}", api_key))
-        .header("Content-Type", "application/json")
-        .json(&request_body)
-        .send()
-        .await;
-
-    match response {
-        Ok(resp) => {
-            if resp.status().is_success() {
-                match resp.json::<serde_json::Value>().await {
-                    Ok(result) => {
-                        if let Some(content) = result["choices"][0]["message"]["content"].as_str() {
-                            Ok(content.trim().to_string())
-                        } else {
-                            Err("No content in AI response".to_string())
-                        }
-                    }
-                    Err(e) => Err(format!("Failed to parse AI response: {}", e))
-                }
-            } else {
-                let status = resp.status();
-                match resp.text().await {
-                    Ok(error_text) => {
-                        // Parse the error response to provide better error messages
-                        if status == 401 {
-                            Err(format!("Authentication failed (401 Unauthorized): {}", error_text))
-                        } else if status == 403 {
-                            Err(format!("Access forbidden (403 Forbidden): {}", error_text))
-                        } else if status == 429 {
-                            Err(format!("Rate limit exceeded (429 Too Many Requests): {}", error_text))
-                        } else {
-                            Err(format!("API request failed ({}): {}", status, error_text))
-                        }
-                    },
-                    Err(_) => Err(format!("API request failed with status: {}", status))
-                }
-            }
+// Expected tokens and cost of a request, so the overlay can show them before sending
+#[tauri::command]
+async fn estimate_request(
+    text: String,
+    action: String,
+    model: String,
+    api_key: String,
+    tone: Option<String>,
+    max_tokens: Option<u32>,
+) -> Result<Estimate, String> {
+    estimate::estimate(EstimateRequest {
+        text: &text,
+        action: &action,
+        model: &model,
+        api_key: &api_key,
+        tone: tone.as_deref(),
+        max_tokens,
+    })
+    .await
+}
+
+// Spend in the current day, week or month across all profiles, by model and by action
+#[tauri::command]
+fn get_usage_summary(period: Period) -> Result<UsageSummary, String> {
+    usage::summary(period)
+}
+
+// The global budget lives outside the profile stores so it applies to every profile
+#[tauri::command]
+fn get_budget(app: tauri::AppHandle, scope: BudgetScope) -> Result<Option<BudgetSettings>, String> {
+    match scope {
+        BudgetScope::Global => budget::global_budget(),
+        BudgetScope::Profile => load_setting(&app, BUDGET_KEY, None),
+    }
+}
+
+#[tauri::command]
+fn set_budget(app: tauri::AppHandle, scope: BudgetScope, budget: BudgetSettings) -> Result<(), String> {
+    budget.validate()?;
+    match scope {
+        BudgetScope::Global => budget::set_global_budget(&budget),
+        BudgetScope::Profile => {
+            save_setting(&app, BUDGET_KEY, &budget)?;
+            budget::set_profile_budget(Some(budget));
+            Ok(())
         }
-        Err(e) => Err(format!("Failed to connect to OpenRouter API: {}", e))
     }
 }
 
+// Spent and remaining amounts for every configured limit
+#[tauri::command]
+fn get_budget_status() -> Result<Vec<BudgetStatus>, String> {
+    budget::status()
+}
+
+// Built-in, local and custom actions, in the order the overlay should list them
+#[tauri::command]
+fn list_actions() -> Vec<ActionInfo> {
+    actions::available_actions()
+}
+
+#[tauri::command]
+fn get_custom_actions(app: tauri::AppHandle) -> Result<Vec<CustomAction>, String> {
+    load_setting(&app, CUSTOM_ACTIONS_KEY, Vec::new())
+}
+
+#[tauri::command]
+async fn set_custom_actions(app: tauri::AppHandle, custom_actions: Vec<CustomAction>) -> Result<(), String> {
+    actions::validate_custom_actions(&custom_actions)?;
+    save_setting(&app, CUSTOM_ACTIONS_KEY, &custom_actions)?;
+    actions::set_custom_actions(custom_actions);
+    Ok(())
+}
+
+#[tauri::command]
+fn get_action_scripts(app: tauri::AppHandle) -> Result<Vec<ActionScript>, String> {
+    load_setting(&app, ACTION_SCRIPTS_KEY, Vec::new())
+}
+
+#[tauri::command]
+async fn set_action_scripts(app: tauri::AppHandle, scripts: Vec<ActionScript>) -> Result<(), String> {
+    scripting::validate(&scripts)?;
+    save_setting(&app, ACTION_SCRIPTS_KEY, &scripts)?;
+    scripting::set_action_scripts(scripts);
+    Ok(())
+}
+
+#[tauri::command]
+fn get_model_preferences(app: tauri::AppHandle) -> Result<ModelPreferences, String> {
+    load_setting(&app, MODEL_PREFERENCES_KEY, ModelPreferences::default())
+}
+
+#[tauri::command]
+async fn set_model_preferences(app: tauri::AppHandle, preferences: ModelPreferences) -> Result<(), String> {
+    preferences.validate()?;
+    save_setting(&app, MODEL_PREFERENCES_KEY, &preferences)?;
+    models::set_preferences(preferences);
+    Ok(())
+}
+
+#[tauri::command]
+fn get_sampling(app: tauri::AppHandle) -> Result<SamplingSettings, String> {
+    load_setting(&app, SAMPLING_KEY, SamplingSettings::default())
+}
+
+#[tauri::command]
+async fn set_sampling(app: tauri::AppHandle, settings: SamplingSettings) -> Result<(), String> {
+    settings.validate()?;
+    save_setting(&app, SAMPLING_KEY, &settings)?;
+    sampling::set_sampling(Some(settings));
+    Ok(())
+}
+
+#[tauri::command]
+fn get_provider_preferences(app: tauri::AppHandle) -> Result<ProviderPreferences, String> {
+    load_setting(&app, PROVIDER_PREFERENCES_KEY, ProviderPreferences::default())
+}
+
+#[tauri::command]
+async fn set_provider_preferences(app: tauri::AppHandle, preferences: ProviderPreferences) -> Result<(), String> {
+    preferences.validate()?;
+    save_setting(&app, PROVIDER_PREFERENCES_KEY, &preferences)?;
+    routing::set_preferences(Some(preferences));
+    Ok(())
+}
+
+#[tauri::command]
+fn get_pipelines(app: tauri::AppHandle) -> Result<Vec<Pipeline>, String> {
+    load_setting(&app, PIPELINES_KEY, Vec::new())
+}
+
+#[tauri::command]
+async fn set_pipelines(app: tauri::AppHandle, pipelines: Vec<Pipeline>) -> Result<(), String> {
+    pipeline::validate(&pipelines)?;
+    save_setting(&app, PIPELINES_KEY, &pipelines)
+}
+
+// Run a saved pipeline in one call, returning every step's result
+#[tauri::command]
+async fn run_pipeline(
+    app: tauri::AppHandle,
+    pipeline_id: String,
+    text: String,
+    model: String,
+    api_key: String,
+    max_tokens: Option<u32>
+) -> Result<PipelineResult, String> {
+    let pipelines = get_pipelines(app)?;
+    let pipeline = pipelines
+        .iter()
+        .find(|p| p.id == pipeline_id)
+        .ok_or_else(|| format!("Unknown pipeline: {}", pipeline_id))?;
+    Ok(pipeline::run(&pipeline.steps, text, &model, &api_key, max_tokens).await)
+}
+
 #[tauri::command]
 async fn resize_window(app: tauri::AppHandle, height: f64) -> Result<(), String> {
     if let Some(window) = app.get_webview_window("main") {
@@ -306,23 +652,55 @@ async fn resize_window(app: tauri::AppHandle, height: f64) -> Result<(), String>
     Ok(())
 }
 
+// Apply the launch flags of a second `wraiter` invocation to the running instance
+fn handle_second_instance(app: &tauri::AppHandle, launch_args: LaunchArgs) {
+    if launch_args.profile.is_some() {
+        eprintln!("Ignoring --profile for an already running instance");
+    }
+    if launch_args.hidden && launch_args.action.is_none() {
+        return;
+    }
+
+    if let Some(action) = launch_args.action {
+        if let Ok(mut pending_action) = app.state::<LaunchState>().pending_action.lock() {
+            *pending_action = Some(action.clone());
+        }
+        let _ = app.emit("launch-action", action);
+    }
+
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.center();
+        let _ = window.set_focus();
+    }
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    let launch_args = LaunchArgs::from_env();
+
     tauri::Builder::default()
+        // Must be registered first so a second launch exits before touching shortcuts or the tray
+        .plugin(tauri_plugin_single_instance::init(|app, argv, _cwd| {
+            handle_second_instance(app, LaunchArgs::parse_from(argv));
+        }))
         .plugin(tauri_plugin_autostart::init(
             MacosLauncher::LaunchAgent,
-            Some(vec!["--flag1", "--flag2"]),
+            Some(vec!["--hidden"]),
         ))
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_clipboard_manager::init())
         .plugin(tauri_plugin_global_shortcut::Builder::new().build())
         .plugin(tauri_plugin_store::Builder::default().build())
+        .plugin(tauri_plugin_notification::init())
         .invoke_handler(tauri::generate_handler![
             show_overlay,
             hide_overlay,
             get_clipboard_text,
+            get_clipboard_image,
             set_clipboard_text,
             fetch_openrouter_models,
+            search_models,
             process_text_with_ai,
             resize_window,
             get_shortcut,
@@ -335,9 +713,87 @@ pub fn run() {
             get_api_key,
             set_auto_close
             ,
-            set_api_key
+            set_api_key,
+            get_clipboard_watcher,
+            set_clipboard_watcher,
+            take_launch_action,
+            get_default_model,
+            set_default_model,
+            get_http_api,
+            set_http_api,
+            regenerate_http_api_token,
+            get_pipelines,
+            set_pipelines,
+            run_pipeline,
+            list_actions,
+            get_custom_actions,
+            set_custom_actions,
+            get_action_scripts,
+            set_action_scripts,
+            get_model_preferences,
+            set_model_preferences,
+            get_sampling,
+            set_sampling,
+            get_provider_preferences,
+            set_provider_preferences,
+            get_key_status,
+            estimate_request,
+            get_usage_summary,
+            get_budget,
+            set_budget,
+            get_budget_status
         ])
-        .setup(|app| {
+        .setup(move |app| {
+            app.manage(LaunchState {
+                profile: launch_args.profile.clone(),
+                pending_action: Mutex::new(launch_args.action.clone()),
+            });
+            usage::set_profile(launch_args.profile.clone());
+            match settings_store(app.handle()) {
+                Ok(store) => cli::load_settings(|key| store.get(key)),
+                Err(e) => eprintln!("Failed to load settings: {}", e),
+            }
+
+            let watcher_settings = load_clipboard_watcher_settings(app.handle()).unwrap_or_else(|e| {
+                eprintln!("Failed to load clipboard watcher settings: {}", e);
+                WatcherSettings::default()
+            });
+            let watcher_enabled = watcher_settings.enabled;
+            app.manage(ClipboardWatcherState(Mutex::new(watcher_settings)));
+            spawn_clipboard_watcher(app.handle().clone());
+
+            app.manage(HttpApiState(Mutex::new(None)));
+            match load_http_api_settings(app.handle()) {
+                Ok(settings) if settings.enabled => {
+                    let app_handle = app.handle().clone();
+                    tauri::async_runtime::spawn(async move {
+                        // Saving generates the token a hand-edited settings file may lack
+                        let result = if settings.token.is_empty() {
+                            set_http_api(app_handle, settings).await.map(|_| ())
+                        } else {
+                            restart_http_api(&app_handle, settings).await
+                        };
+                        if let Err(e) = result {
+                            eprintln!("Failed to start HTTP API: {}", e);
+                        }
+                    });
+                }
+                Ok(_) => {}
+                Err(e) => eprintln!("Failed to load HTTP API settings: {}", e),
+            }
+
+            #[cfg(target_os = "linux")]
+            {
+                let credentials = stored_credentials(app.handle());
+                let overlay_app = app.handle().clone();
+                let show: dbus::ShowOverlay = Arc::new(move || Box::pin(show_overlay(overlay_app.clone())));
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = dbus::serve(credentials, show).await {
+                        eprintln!("{}", e);
+                    }
+                });
+            }
+
             let app_handle = app.handle().clone();
             tauri::async_runtime::spawn(async move {
                 let shortcut = get_shortcut(app_handle.clone()).unwrap_or_else(|_| DEFAULT_SHORTCUT.to_string());
@@ -350,8 +806,9 @@ pub fn run() {
             let is_enabled = autostart_manager.is_enabled().unwrap_or(false);
             let show = MenuItem::with_id(app, "show", "Show UI", true, None::<&str>)?;
             let startup = CheckMenuItem::with_id(app, "startup", "Start on Boot", true, is_enabled, None::<&str>)?;
+            let watcher = CheckMenuItem::with_id(app, "clipboard_watcher", "Watch Clipboard", true, watcher_enabled, None::<&str>)?;
             let exit = MenuItem::with_id(app, "exit", "Exit App", true, None::<&str>)?;
-            let menu = Menu::with_items(app, &[&show, &startup, &exit])?;
+            let menu = Menu::with_items(app, &[&show, &startup, &watcher, &exit])?;
             let _tray = TrayIconBuilder::new()
                 .icon(app.default_window_icon().unwrap().clone())
                 .menu(&menu)
@@ -373,6 +830,25 @@ pub fn run() {
                                 }
                             }
                         }
+                        "clipboard_watcher" => {
+                            let app_handle = app.clone();
+                            tauri::async_runtime::spawn(async move {
+                                let mut settings = match get_clipboard_watcher(app_handle.clone()) {
+                                    Ok(settings) => settings,
+                                    Err(e) => {
+                                        eprintln!("Failed to toggle clipboard watcher: {}", e);
+                                        return;
+                                    }
+                                };
+                                settings.enabled = !settings.enabled;
+                                if settings.enabled && settings.rules.is_empty() {
+                                    notify(&app_handle, "Clipboard watcher", "No rules are set up yet. Add them under Clipboard Watcher in App Settings.");
+                                }
+                                if let Err(e) = set_clipboard_watcher(app_handle, settings).await {
+                                    eprintln!("Failed to toggle clipboard watcher: {}", e);
+                                }
+                            });
+                        }
                         "exit" => {
                             app.exit(0);
                         }
@@ -382,11 +858,19 @@ pub fn run() {
                 .build(app)?;
 
             let window = app.get_webview_window("main").unwrap();
-            
-            // Check if autostart is enabled and hide window if so
-            if let Ok(is_enabled) = app.autolaunch().is_enabled() {
-                if is_enabled {
-                    let _ = window.hide();
+
+            // Autostart launches pass --hidden; manual launches and --action show the window
+            if launch_args.hidden && launch_args.action.is_none() {
+                let _ = window.hide();
+            } else {
+                let _ = window.show();
+                let _ = window.set_focus();
+            }
+
+            // Re-register autostart so entries created by older versions pick up --hidden
+            if is_enabled {
+                if let Err(e) = autostart_manager.enable() {
+                    eprintln!("Failed to refresh autostart entry: {}", e);
                 }
             }
             
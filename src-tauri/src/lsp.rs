@@ -0,0 +1,423 @@
+// Language Server Protocol server over stdio offering the actions as code actions
+// on selected ranges in Markdown and plain text documents
+use crate::actions::{self, ActionRequest};
+use crate::cli;
+use crate::transforms;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::{mpsc, oneshot};
+
+const SUPPORTED_LANGUAGES: &[&str] = &["markdown", "plaintext"];
+const CODE_ACTION_KIND: &str = "refactor.rewrite";
+const RUN_ACTION_COMMAND: &str = "wraiter.runAction";
+
+// JSON-RPC and LSP error codes
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const REQUEST_CANCELLED: i64 = -32800;
+const CONTENT_MODIFIED: i64 = -32801;
+const REQUEST_FAILED: i64 = -32803;
+
+// Code actions offered for a selection: title, action id and tone
+const CODE_ACTIONS: &[(&str, &str, Option<&str>)] = &[
+    ("Proofread selection", "proofread", None),
+    ("Rewrite in formal tone", "tone", Some("formal")),
+    ("Rewrite in casual tone", "tone", Some("casual")),
+    ("Rewrite in friendly tone", "tone", Some("friendly")),
+    ("Improve and expand selection", "draft", None),
+];
+
+struct Document {
+    language_id: String,
+    version: i64,
+    text: String,
+}
+
+struct Server {
+    documents: HashMap<String, Document>,
+    // Whether the client can resolve the edit lazily via codeAction/resolve
+    resolve_edits: bool,
+    // Whether the client accepts versioned edits, which it refuses once the document changed
+    document_changes: bool,
+    next_request_id: i64,
+    // Requests waiting for a model call, by id, so $/cancelRequest can stop them
+    running: HashMap<String, oneshot::Sender<()>>,
+    outgoing: mpsc::UnboundedSender<Value>,
+}
+
+// The selected text of an open document, taken when the request arrives so the model call
+// doesn't hold on to the server
+struct Selection {
+    uri: String,
+    version: i64,
+    range: Value,
+    text: String,
+    action: String,
+    tone: Option<String>,
+    document_changes: bool,
+}
+
+pub async fn serve() -> Result<(), String> {
+    let mut reader = BufReader::new(tokio::io::stdin());
+    // Responses are written by one task, as model calls finish in the background in any order
+    let (outgoing, mut queue) = mpsc::unbounded_channel::<Value>();
+    let writer = tokio::spawn(async move {
+        let mut stdout = tokio::io::stdout();
+        while let Some(message) = queue.recv().await {
+            write_message(&mut stdout, &message).await?;
+        }
+        Ok::<(), String>(())
+    });
+    let mut server = Server {
+        documents: HashMap::new(),
+        resolve_edits: false,
+        document_changes: false,
+        next_request_id: 1,
+        running: HashMap::new(),
+        outgoing,
+    };
+
+    while let Some(message) = read_message(&mut reader).await? {
+        let Some(method) = message["method"].as_str() else {
+            // Responses to our workspace/applyEdit requests need no handling
+            continue;
+        };
+        let params = &message["params"];
+        let Some(id) = message.get("id").cloned() else {
+            if method == "exit" {
+                break;
+            }
+            server.handle_notification(method, params);
+            continue;
+        };
+        server.handle_request(id, method, params);
+    }
+
+    // Cancels the model calls still running, and the writer ends once their answers are sent
+    drop(server);
+    writer.await.map_err(|e| e.to_string())?
+}
+
+impl Server {
+    fn handle_notification(&mut self, method: &str, params: &Value) {
+        match method {
+            "textDocument/didOpen" => {
+                let document = &params["textDocument"];
+                if let Some(uri) = document["uri"].as_str() {
+                    self.documents.insert(uri.to_string(), Document {
+                        language_id: document["languageId"].as_str().unwrap_or_default().to_string(),
+                        version: document["version"].as_i64().unwrap_or_default(),
+                        text: document["text"].as_str().unwrap_or_default().to_string(),
+                    });
+                }
+            }
+            "textDocument/didChange" => {
+                // Documents are synced in full, so the last change holds the whole text
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                let text = params["contentChanges"]
+                    .as_array()
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change["text"].as_str());
+                if let (Some(document), Some(text)) = (self.documents.get_mut(uri), text) {
+                    document.text = text.to_string();
+                    document.version = params["textDocument"]["version"].as_i64().unwrap_or(document.version + 1);
+                }
+            }
+            "textDocument/didClose" => {
+                if let Some(uri) = params["textDocument"]["uri"].as_str() {
+                    self.documents.remove(uri);
+                }
+            }
+            "$/cancelRequest" => {
+                if let Some(cancel) = self.running.remove(&params["id"].to_string()) {
+                    let _ = cancel.send(());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_request(&mut self, id: Value, method: &str, params: &Value) {
+        let result = match method {
+            "initialize" => Ok(self.initialize(params)),
+            "shutdown" => Ok(Value::Null),
+            "textDocument/codeAction" => Ok(self.code_actions(params)),
+            "codeAction/resolve" => {
+                let mut code_action = params.clone();
+                return self.spawn_edit(id, &params["data"], move |edit| {
+                    code_action["edit"] = edit;
+                    code_action
+                });
+            }
+            "workspace/executeCommand" if params["command"].as_str() == Some(RUN_ACTION_COMMAND) => {
+                let request_id = self.next_request_id;
+                self.next_request_id += 1;
+                let outgoing = self.outgoing.clone();
+                return self.spawn_edit(id, &params["arguments"][0], move |edit| {
+                    let _ = outgoing.send(json!({
+                        "jsonrpc": "2.0",
+                        "id": request_id,
+                        "method": "workspace/applyEdit",
+                        "params": { "label": "Wraiter", "edit": edit }
+                    }));
+                    Value::Null
+                });
+            }
+            "workspace/executeCommand" => Err((INVALID_PARAMS, "Unknown command".to_string())),
+            _ => Err((METHOD_NOT_FOUND, format!("Method not found: {}", method))),
+        };
+        let _ = self.outgoing.send(response(id, result));
+    }
+
+    // Run the action on a task of its own so the message loop keeps reading cancellations and
+    // changes meanwhile, and answer the request with `finish` applied to the edit
+    fn spawn_edit(&mut self, id: Value, data: &Value, finish: impl FnOnce(Value) -> Value + Send + 'static) {
+        let selection = match self.selection(data) {
+            Ok(selection) => selection,
+            Err(error) => {
+                let _ = self.outgoing.send(response(id, Err(error)));
+                return;
+            }
+        };
+        let (cancel, cancelled) = oneshot::channel();
+        self.running.retain(|_, cancel| !cancel.is_closed());
+        self.running.insert(id.to_string(), cancel);
+        let outgoing = self.outgoing.clone();
+        tokio::spawn(async move {
+            let result = tokio::select! {
+                edit = selection.edit() => edit.map(finish),
+                _ = cancelled => Err((REQUEST_CANCELLED, "Request cancelled".to_string())),
+            };
+            let _ = outgoing.send(response(id, result));
+        });
+    }
+
+    fn initialize(&mut self, params: &Value) -> Value {
+        self.resolve_edits = params["capabilities"]["textDocument"]["codeAction"]["resolveSupport"]["properties"]
+            .as_array()
+            .is_some_and(|properties| properties.iter().any(|p| p.as_str() == Some("edit")));
+        self.document_changes = params["capabilities"]["workspace"]["workspaceEdit"]["documentChanges"]
+            .as_bool()
+            .unwrap_or(false);
+        json!({
+            "capabilities": {
+                // Full document sync
+                "textDocumentSync": 1,
+                "codeActionProvider": {
+                    "codeActionKinds": [CODE_ACTION_KIND],
+                    "resolveProvider": true
+                },
+                "executeCommandProvider": {
+                    "commands": [RUN_ACTION_COMMAND]
+                }
+            },
+            "serverInfo": {
+                "name": "wraiter",
+                "version": env!("CARGO_PKG_VERSION")
+            }
+        })
+    }
+
+    fn code_actions(&self, params: &Value) -> Value {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        let range = &params["range"];
+        let Some(document) = self.documents.get(uri) else {
+            return json!([]);
+        };
+        if !SUPPORTED_LANGUAGES.contains(&document.language_id.as_str()) || range["start"] == range["end"] {
+            return json!([]);
+        }
+
+        // Local transforms and custom actions are offered after the built-in rewrites
+        let custom_actions = actions::custom_actions();
+        let offered = CODE_ACTIONS
+            .iter()
+            .map(|(title, action, tone)| (title.to_string(), action.to_string(), tone.map(str::to_string)))
+            .chain(transforms::TRANSFORMS.iter().map(|transform| (transform.title.to_string(), transform.id.to_string(), None)))
+            .chain(custom_actions.into_iter().map(|action| (action.name, action.id, None)));
+
+        let code_actions: Vec<Value> = offered
+            .map(|(title, action, tone)| {
+                let data = json!({
+                    "uri": uri,
+                    "version": document.version,
+                    "range": range,
+                    "action": action,
+                    "tone": tone
+                });
+                if self.resolve_edits {
+                    json!({ "title": title, "kind": CODE_ACTION_KIND, "data": data })
+                } else {
+                    json!({
+                        "title": title,
+                        "kind": CODE_ACTION_KIND,
+                        "command": { "title": title, "command": RUN_ACTION_COMMAND, "arguments": [data] }
+                    })
+                }
+            })
+            .collect();
+        Value::Array(code_actions)
+    }
+
+    // The text a code action applies to, as long as the document hasn't changed since it was offered
+    fn selection(&self, data: &Value) -> Result<Selection, (i64, String)> {
+        let uri = data["uri"].as_str().ok_or((INVALID_PARAMS, "Missing document uri".to_string()))?;
+        let action = data["action"].as_str().ok_or((INVALID_PARAMS, "Missing action".to_string()))?;
+        let range = &data["range"];
+        let document = self
+            .documents
+            .get(uri)
+            .ok_or((INVALID_PARAMS, format!("Document is not open: {}", uri)))?;
+        if data["version"].as_i64().is_some_and(|version| version != document.version) {
+            return Err((CONTENT_MODIFIED, "The document changed since the action was offered".to_string()));
+        }
+        let start = offset_at(&document.text, &range["start"]).ok_or((INVALID_PARAMS, "Invalid range".to_string()))?;
+        let end = offset_at(&document.text, &range["end"]).ok_or((INVALID_PARAMS, "Invalid range".to_string()))?;
+        if start >= end {
+            return Err((INVALID_PARAMS, "Invalid range".to_string()));
+        }
+        Ok(Selection {
+            uri: uri.to_string(),
+            version: document.version,
+            range: range.clone(),
+            text: document.text[start..end].to_string(),
+            action: action.to_string(),
+            tone: data["tone"].as_str().map(str::to_string),
+            document_changes: self.document_changes,
+        })
+    }
+}
+
+impl Selection {
+    // Run the action on the selected text and build the edit replacing it
+    async fn edit(self) -> Result<Value, (i64, String)> {
+        let api_key = cli::stored_api_key().unwrap_or_default();
+        let output = actions::run_action(ActionRequest {
+            text: self.text,
+            action: self.action,
+            model: cli::resolve_model(None),
+            api_key,
+            tone: self.tone,
+            max_tokens: None,
+            image: None,
+        })
+        .await
+        .map_err(|e| (REQUEST_FAILED, e.to_string()))?;
+
+        let edit = json!({ "range": self.range, "newText": output.text });
+        // A versioned edit is refused by the client if the document changed during the call
+        Ok(if self.document_changes {
+            json!({
+                "documentChanges": [{
+                    "textDocument": { "uri": self.uri, "version": self.version },
+                    "edits": [edit]
+                }]
+            })
+        } else {
+            json!({ "changes": { self.uri: [edit] } })
+        })
+    }
+}
+
+fn response(id: Value, result: Result<Value, (i64, String)>) -> Value {
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err((code, message)) => json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } }),
+    }
+}
+
+// Convert an LSP position (line and UTF-16 column) into a byte offset. Lines end with
+// \n, \r\n or \r, none of which count as part of the line.
+fn offset_at(text: &str, position: &Value) -> Option<usize> {
+    let line = position["line"].as_u64()? as usize;
+    let character = position["character"].as_u64()? as usize;
+
+    let mut line_start = 0;
+    for _ in 0..line {
+        let line_end = line_start + text[line_start..].find(['\r', '\n'])?;
+        line_start = line_end + if text[line_end..].starts_with("\r\n") { 2 } else { 1 };
+    }
+    let rest = &text[line_start..];
+    let line_text = &rest[..rest.find(['\r', '\n']).unwrap_or(rest.len())];
+
+    let mut utf16_column = 0;
+    for (index, c) in line_text.char_indices() {
+        if utf16_column >= character {
+            return Some(line_start + index);
+        }
+        utf16_column += c.len_utf16();
+    }
+    Some(line_start + line_text.len())
+}
+async fn read_message(reader: &mut (impl AsyncBufReadExt + Unpin)) -> Result<Option<Value>, String> {
+    let mut content_length = None;
+    loop {
+        let mut header = String::new();
+        let read = reader
+            .read_line(&mut header)
+            .await
+            .map_err(|e| format!("Failed to read stdin: {}", e))?;
+        if read == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(length) = header.strip_prefix("Content-Length:") {
+            content_length = length.trim().parse::<usize>().ok();
+        }
+    }
+
+    let content_length = content_length.ok_or("Missing Content-Length header")?;
+    let mut body = vec![0; content_length];
+    reader
+        .read_exact(&mut body)
+        .await
+        .map_err(|e| format!("Failed to read stdin: {}", e))?;
+    // Malformed messages are skipped rather than ending the session
+    Ok(Some(serde_json::from_slice(&body).unwrap_or(Value::Null)))
+}
+
+async fn write_message(writer: &mut (impl AsyncWrite + Unpin), message: &Value) -> Result<(), String> {
+    let body = message.to_string();
+    let frame = format!("Content-Length: {}\r\n\r\n{}", body.len(), body);
+    writer
+        .write_all(frame.as_bytes())
+        .await
+        .map_err(|e| format!("Failed to write stdout: {}", e))?;
+    writer.flush().await.map_err(|e| format!("Failed to write stdout: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn offset(text: &str, line: u64, character: u64) -> Option<usize> {
+        offset_at(text, &json!({ "line": line, "character": character }))
+    }
+
+    #[test]
+    fn offset_at_counts_utf16_columns() {
+        let text = "a\u{e9}\u{1F600}b\nnext";
+        assert_eq!(offset(text, 0, 1), Some(1));
+        assert_eq!(offset(text, 0, 2), Some(3));
+        // The emoji takes two UTF-16 units and four bytes
+        assert_eq!(offset(text, 0, 4), Some(7));
+        assert_eq!(&text[offset(text, 0, 4).unwrap()..offset(text, 1, 0).unwrap()], "b\n");
+        assert_eq!(offset(text, 1, 2), Some(11));
+    }
+
+    #[test]
+    fn offset_at_ends_lines_before_crlf() {
+        let text = "first\r\nsecond\rthird\n";
+        // Columns past the end stop before the line break
+        assert_eq!(offset(text, 0, 99), Some(5));
+        assert_eq!(offset(text, 1, 0), Some(7));
+        assert_eq!(offset(text, 1, 99), Some(13));
+        assert_eq!(offset(text, 2, 0), Some(14));
+        assert_eq!(offset(text, 3, 0), Some(text.len()));
+        assert_eq!(offset(text, 4, 0), None);
+    }
+}
@@ -2,5 +2,8 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
+    if let Some(exit_code) = wraiter_lib::cli::run() {
+        std::process::exit(exit_code);
+    }
     wraiter_lib::run()
 }
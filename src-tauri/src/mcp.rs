@@ -0,0 +1,195 @@
+// Model Context Protocol server over stdio exposing the actions as tools and the history of
+// model calls as resources
+use crate::actions::{self, ActionRequest};
+use crate::cli;
+use crate::usage::{self, Period};
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+const PROTOCOL_VERSION: &str = "2024-11-05";
+const SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &["2024-11-05", "2025-03-26", "2025-06-18"];
+
+// JSON-RPC error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INTERNAL_ERROR: i64 = -32603;
+const RESOURCE_NOT_FOUND: i64 = -32002;
+
+const HISTORY_URI: &str = "wraiter://history";
+const SPEND_URI: &str = "wraiter://spend/month";
+// Most recent ledger entries returned by the history resource
+const HISTORY_LIMIT: usize = 100;
+
+// Read newline-delimited JSON-RPC messages from stdin until it is closed
+pub async fn serve() -> Result<(), String> {
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    let mut stdout = tokio::io::stdout();
+
+    while let Some(line) = lines.next_line().await.map_err(|e| format!("Failed to read stdin: {}", e))? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str::<Value>(&line) {
+            Ok(message) => handle_message(message).await,
+            Err(e) => Some(error_response(Value::Null, PARSE_ERROR, &format!("Parse error: {}", e))),
+        };
+        if let Some(response) = response {
+            let mut output = response.to_string();
+            output.push('\n');
+            stdout
+                .write_all(output.as_bytes())
+                .await
+                .map_err(|e| format!("Failed to write stdout: {}", e))?;
+            stdout.flush().await.map_err(|e| format!("Failed to write stdout: {}", e))?;
+        }
+    }
+    Ok(())
+}
+
+// Handle one request or notification, returning the response to send if any
+async fn handle_message(message: Value) -> Option<Value> {
+    let Some(method) = message["method"].as_str() else {
+        return Some(error_response(message["id"].clone(), INVALID_REQUEST, "Invalid request"));
+    };
+    // Notifications carry no id and never get a response
+    let id = message.get("id")?.clone();
+    let params = &message["params"];
+
+    let result = match method {
+        "initialize" => Ok(initialize(params)),
+        "ping" => Ok(json!({})),
+        "tools/list" => Ok(json!({ "tools": tool_definitions() })),
+        "tools/call" => call_tool(params).await,
+        "resources/list" => Ok(json!({ "resources": resource_definitions() })),
+        "resources/read" => read_resource(params),
+        "prompts/list" => Ok(json!({ "prompts": [] })),
+        _ => Err((METHOD_NOT_FOUND, format!("Method not found: {}", method))),
+    };
+
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err((code, message)) => error_response(id, code, &message),
+    })
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+fn initialize(params: &Value) -> Value {
+    let requested = params["protocolVersion"].as_str().unwrap_or(PROTOCOL_VERSION);
+    let protocol_version = if SUPPORTED_PROTOCOL_VERSIONS.contains(&requested) {
+        requested
+    } else {
+        PROTOCOL_VERSION
+    };
+    json!({
+        "protocolVersion": protocol_version,
+        "capabilities": {
+            "tools": { "listChanged": false },
+            "resources": { "subscribe": false, "listChanged": false }
+        },
+        "serverInfo": {
+            "name": "wraiter",
+            "version": env!("CARGO_PKG_VERSION")
+        }
+    })
+}
+
+fn tool_definitions() -> Vec<Value> {
+    actions::available_actions()
+        .into_iter()
+        .map(|action| {
+            let mut properties = json!({
+                "text": { "type": "string", "description": "Text to process" },
+                "model": { "type": "string", "description": "OpenRouter model id, defaults to the model selected in Wraiter" }
+            });
+            if action.id == "tone" {
+                properties["tone"] = json!({ "type": "string", "description": "Tone to rewrite in, e.g. casual, formal, friendly" });
+            }
+            json!({
+                "name": action.id,
+                "title": action.title,
+                "description": action.description,
+                "inputSchema": {
+                    "type": "object",
+                    "properties": properties,
+                    "required": ["text"]
+                }
+            })
+        })
+        .collect()
+}
+
+async fn call_tool(params: &Value) -> Result<Value, (i64, String)> {
+    let name = params["name"]
+        .as_str()
+        .ok_or((INVALID_PARAMS, "Missing tool name".to_string()))?;
+    if !actions::is_known_action(name) {
+        return Err((INVALID_PARAMS, format!("Unknown tool: {}", name)));
+    }
+    let arguments = &params["arguments"];
+    let text = arguments["text"]
+        .as_str()
+        .ok_or((INVALID_PARAMS, "Missing required argument: text".to_string()))?;
+
+    // Failures of the action itself are reported as tool errors, not protocol errors
+    let result = actions::run_action(ActionRequest {
+        text: text.to_string(),
+        action: name.to_string(),
+        model: cli::resolve_model(arguments["model"].as_str().map(str::to_string)),
+        api_key: cli::stored_api_key().unwrap_or_default(),
+        tone: arguments["tone"].as_str().map(str::to_string),
+        max_tokens: None,
+        image: None,
+    })
+    .await;
+
+    Ok(match result {
+        Ok(output) => json!({ "content": [{ "type": "text", "text": output.text }], "isError": false }),
+        Err(e) => json!({ "content": [{ "type": "text", "text": e.to_string() }], "isError": true }),
+    })
+}
+
+fn resource_definitions() -> Vec<Value> {
+    vec![
+        json!({
+            "uri": HISTORY_URI,
+            "name": "history",
+            "title": "Recent model calls",
+            "description": format!("The last {} model calls with their action, model, tokens and cost", HISTORY_LIMIT),
+            "mimeType": "application/json"
+        }),
+        json!({
+            "uri": SPEND_URI,
+            "name": "spend",
+            "title": "Spend this month",
+            "description": "Tokens and cost of this month's model calls, in total and by model and action",
+            "mimeType": "application/json"
+        }),
+    ]
+}
+
+fn read_resource(params: &Value) -> Result<Value, (i64, String)> {
+    let uri = params["uri"]
+        .as_str()
+        .ok_or((INVALID_PARAMS, "Missing resource uri".to_string()))?;
+    let contents = match uri {
+        HISTORY_URI => {
+            let mut records = usage::records_since(0).map_err(|e| (INTERNAL_ERROR, e))?;
+            records.drain(..records.len().saturating_sub(HISTORY_LIMIT));
+            json!(records)
+        }
+        SPEND_URI => json!(usage::summary(Period::Month).map_err(|e| (INTERNAL_ERROR, e))?),
+        _ => return Err((RESOURCE_NOT_FOUND, format!("Resource not found: {}", uri))),
+    };
+    Ok(json!({
+        "contents": [{
+            "uri": uri,
+            "mimeType": "application/json",
+            "text": contents.to_string()
+        }]
+    }))
+}
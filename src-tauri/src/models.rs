@@ -0,0 +1,317 @@
+// The OpenRouter model catalog, cached on disk so the app and the CLI don't download
+// it for every cost lookup, plus filtering for the model pickers and the user's aliases
+use crate::cli;
+use crate::error::CallError;
+use crate::openrouter::{self, Model, ModelList, Pricing};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+
+const CACHE_FILE: &str = "models.json";
+// How long the cached catalog is used before asking OpenRouter whether it changed
+const CACHE_TTL_SECS: i64 = 6 * 60 * 60;
+
+static CATALOG: RwLock<Option<ModelCache>> = RwLock::new(None);
+// Favorites and aliases of the running profile
+static PREFERENCES: RwLock<ModelPreferences> = RwLock::new(ModelPreferences {
+    favorites: Vec::new(),
+    aliases: BTreeMap::new(),
+});
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModelPreferences {
+    // Model ids or aliases listed first in the model pickers
+    #[serde(default)]
+    pub favorites: Vec<String>,
+    // Short names for model ids, e.g. "fast" -> "google/gemini-2.0-flash-001"
+    #[serde(default)]
+    pub aliases: BTreeMap<String, String>,
+}
+
+impl ModelPreferences {
+    pub fn validate(&self) -> Result<(), String> {
+        for (alias, model) in &self.aliases {
+            if alias.trim().is_empty() {
+                return Err("Model aliases need a name".to_string());
+            }
+            // Model ids always contain a slash, so an alias with one could shadow a model
+            if alias.contains('/') {
+                return Err(format!("Model alias '{}' must not contain '/'", alias));
+            }
+            if model.trim().is_empty() {
+                return Err(format!("Model alias '{}' has no model", alias));
+            }
+            if self.aliases.contains_key(model) {
+                return Err(format!("Model alias '{}' points to another alias", alias));
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn set_preferences(preferences: ModelPreferences) {
+    if let Ok(mut current) = PREFERENCES.write() {
+        *current = preferences;
+    }
+}
+
+// The model id an alias stands for, or the given id unchanged if it isn't an alias
+pub fn resolve(model: &str) -> String {
+    PREFERENCES
+        .read()
+        .ok()
+        .and_then(|preferences| preferences.aliases.get(model.trim()).cloned())
+        .unwrap_or_else(|| model.to_string())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ModelCache {
+    // Unix timestamp in seconds of the last download or successful revalidation
+    fetched_at: i64,
+    etag: Option<String>,
+    models: Vec<Model>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, clap::Args)]
+#[serde(default)]
+pub struct ModelFilter {
+    /// Only models whose id or name contains this text
+    #[arg(long)]
+    pub query: Option<String>,
+    /// Highest price in USD per million prompt tokens
+    #[arg(long)]
+    pub max_prompt_price: Option<f64>,
+    /// Highest price in USD per million completion tokens
+    #[arg(long)]
+    pub max_completion_price: Option<f64>,
+    /// Smallest context window in tokens
+    #[arg(long)]
+    pub min_context_length: Option<u64>,
+    /// Only models that cost nothing
+    #[arg(long)]
+    pub free_only: bool,
+    /// Only models that accept this input, e.g. "image" or "file"
+    #[arg(long)]
+    pub input_modality: Option<String>,
+}
+
+impl ModelFilter {
+    pub fn matches(&self, model: &Model) -> bool {
+        if let Some(query) = self.query.as_deref().map(str::to_lowercase).filter(|query| !query.is_empty()) {
+            if !model.id.to_lowercase().contains(&query) && !model.name.to_lowercase().contains(&query) {
+                return false;
+            }
+        }
+        // Prices are compared per million tokens, as OpenRouter lists them. Models without
+        // pricing, or with the negative placeholder the router models use, never match.
+        let per_million = |price: f64| (price >= 0.0).then_some(price * 1_000_000.0);
+        if let Some(max) = self.max_prompt_price {
+            match model.pricing.and_then(|pricing| per_million(pricing.prompt)) {
+                Some(price) if price <= max => {}
+                _ => return false,
+            }
+        }
+        if let Some(max) = self.max_completion_price {
+            match model.pricing.and_then(|pricing| per_million(pricing.completion)) {
+                Some(price) if price <= max => {}
+                _ => return false,
+            }
+        }
+        if let Some(min) = self.min_context_length {
+            if model.context_length.unwrap_or(0) < min {
+                return false;
+            }
+        }
+        if self.free_only && !model.is_free() {
+            return false;
+        }
+        if let Some(modality) = &self.input_modality {
+            if !model.accepts_input(modality) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+// The full catalog, refreshed first if the cache has expired
+pub async fn models(api_key: &str) -> Result<Vec<Model>, CallError> {
+    refresh(api_key).await?;
+    Ok(cached(|cache| cache.models.clone()).unwrap_or_default())
+}
+
+pub async fn search(api_key: &str, filter: &ModelFilter) -> Result<Vec<Model>, CallError> {
+    refresh(api_key).await?;
+    Ok(cached(|cache| cache.models.iter().filter(|model| filter.matches(model)).cloned().collect())
+        .unwrap_or_default())
+}
+
+// A single model, None if it isn't in the catalog or the catalog can't be loaded
+pub async fn find(api_key: &str, id: &str) -> Option<Model> {
+    lookup(api_key, id).await.ok().flatten()
+}
+
+// A single model, None if it isn't in the catalog
+pub async fn lookup(api_key: &str, id: &str) -> Result<Option<Model>, CallError> {
+    refresh(api_key).await?;
+    Ok(cached(|cache| cache.models.iter().find(|model| model.id == id).cloned()).flatten())
+}
+
+// The highest price of each kind in the catalog, to count calls whose cost is unknown at the
+// worst case. Only reads the catalog already loaded or cached on disk.
+pub fn highest_pricing() -> Option<Pricing> {
+    if cached(|_| ()).is_none() {
+        store(load_cache()?);
+    }
+    cached(|cache| {
+        cache
+            .models
+            .iter()
+            .filter_map(|model| model.pricing)
+            .fold(None, |highest: Option<Pricing>, pricing| {
+                let highest = highest.unwrap_or_default();
+                // Router models list negative placeholder prices
+                Some(Pricing {
+                    prompt: highest.prompt.max(pricing.prompt),
+                    completion: highest.completion.max(pricing.completion),
+                    request: highest.request.max(pricing.request),
+                })
+            })
+    })
+    .flatten()
+}
+
+fn cached<T>(read: impl FnOnce(&ModelCache) -> T) -> Option<T> {
+    CATALOG.read().ok()?.as_ref().map(read)
+}
+
+async fn refresh(api_key: &str) -> Result<(), CallError> {
+    if cached(|_| ()).is_none() {
+        // Another process may have refreshed the catalog recently
+        if let Some(cache) = load_cache() {
+            store(cache);
+        }
+    }
+
+    let now = Utc::now().timestamp();
+    let (etag, fresh) = cached(|cache| (cache.etag.clone(), now - cache.fetched_at < CACHE_TTL_SECS))
+        .unwrap_or((None, false));
+    if fresh {
+        return Ok(());
+    }
+
+    let cache = match openrouter::fetch_models(api_key, etag.as_deref()).await {
+        Ok(ModelList::Models { models, etag }) => ModelCache {
+            fetched_at: now,
+            etag,
+            models,
+        },
+        Ok(ModelList::NotModified) => match cached(|cache| cache.clone()) {
+            Some(cache) => ModelCache { fetched_at: now, ..cache },
+            None => return Err("OpenRouter reported the model list unchanged but none is cached".into()),
+        },
+        // An outdated catalog is better than none when OpenRouter can't be reached
+        Err(e) if cached(|_| ()).is_some() => {
+            eprintln!("Using the cached model list, refreshing it failed: {}", e);
+            return Ok(());
+        }
+        Err(e) => return Err(e),
+    };
+    if let Err(e) = save_cache(&cache) {
+        eprintln!("Failed to cache the model list: {}", e);
+    }
+    store(cache);
+    Ok(())
+}
+
+fn store(cache: ModelCache) {
+    if let Ok(mut current) = CATALOG.write() {
+        *current = Some(cache);
+    }
+}
+
+// A missing or unreadable cache is simply downloaded again
+fn load_cache() -> Option<ModelCache> {
+    let contents = std::fs::read_to_string(cli::app_data_path(CACHE_FILE)?).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+fn save_cache(cache: &ModelCache) -> Result<(), String> {
+    let path = cli::app_data_path(CACHE_FILE).ok_or("Failed to locate the app data directory")?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let contents = serde_json::to_string(cache).map_err(|e| e.to_string())?;
+    std::fs::write(&path, contents).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model(id: &str, pricing: Option<(f64, f64)>, context_length: u64, input_modalities: &[&str]) -> Model {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "name": id.to_uppercase(),
+            "context_length": context_length,
+            "pricing": pricing.map(|(prompt, completion)| Pricing {
+                prompt,
+                completion,
+                request: 0.0,
+            }),
+            "input_modalities": input_modalities,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn model_filter_matches_query_and_context() {
+        let model = model("vendor/large-model", Some((1e-6, 2e-6)), 128_000, &["text", "image"]);
+        let filter = |filter: ModelFilter| filter.matches(&model);
+        assert!(filter(ModelFilter::default()));
+        assert!(filter(ModelFilter {
+            query: Some("LARGE".to_string()),
+            ..ModelFilter::default()
+        }));
+        assert!(!filter(ModelFilter {
+            query: Some("small".to_string()),
+            ..ModelFilter::default()
+        }));
+        assert!(filter(ModelFilter {
+            min_context_length: Some(128_000),
+            input_modality: Some("Image".to_string()),
+            ..ModelFilter::default()
+        }));
+        assert!(!filter(ModelFilter {
+            input_modality: Some("audio".to_string()),
+            ..ModelFilter::default()
+        }));
+    }
+
+    #[test]
+    fn model_filter_compares_prices_per_million_tokens() {
+        let paid = model("a/paid", Some((1e-6, 2e-6)), 8_000, &[]);
+        let free = model("a/free", Some((0.0, 0.0)), 8_000, &[]);
+        let router = model("a/router", Some((-1.0, -1.0)), 8_000, &[]);
+        let unpriced = model("a/unpriced", None, 8_000, &[]);
+        let cheap = ModelFilter {
+            max_prompt_price: Some(1.0),
+            max_completion_price: Some(2.0),
+            ..ModelFilter::default()
+        };
+        assert!(cheap.matches(&paid) && cheap.matches(&free));
+        // Models without a real price never match a price limit
+        assert!(!cheap.matches(&router) && !cheap.matches(&unpriced));
+        let cheaper = ModelFilter {
+            max_completion_price: Some(1.5),
+            ..ModelFilter::default()
+        };
+        assert!(!cheaper.matches(&paid));
+        let free_only = ModelFilter {
+            free_only: true,
+            ..ModelFilter::default()
+        };
+        assert!(free_only.matches(&free) && !free_only.matches(&paid) && !free_only.matches(&unpriced));
+    }
+}
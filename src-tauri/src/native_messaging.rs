@@ -0,0 +1,267 @@
+// Chrome/Firefox native messaging host: length-prefixed JSON over stdio
+use crate::actions::{self, ActionRequest};
+use crate::cli;
+use crate::models;
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+pub const HOST_NAME: &str = "com.crushy.wraiter";
+const HOST_DESCRIPTION: &str = "Wraiter AI quick actions";
+
+// Browsers refuse messages from the host larger than 1 MB
+const MAX_OUTGOING_MESSAGE: usize = 1024 * 1024;
+const MAX_INCOMING_MESSAGE: usize = 64 * 1024 * 1024;
+
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Browser {
+    Chrome,
+    Chromium,
+    Firefox,
+}
+
+// Browsers start the host with the caller's origin (Chrome) or the manifest path
+// and extension id (Firefox) as arguments, which can't be configured
+pub fn is_browser_invocation(args: &[String]) -> bool {
+    match args.get(1) {
+        Some(arg) if arg.starts_with("chrome-extension://") => true,
+        Some(arg) => arg.ends_with(&format!("{}.json", HOST_NAME)) && args.len() >= 3,
+        None => false,
+    }
+}
+
+pub async fn serve() -> Result<(), String> {
+    let mut stdin = tokio::io::stdin();
+    let mut stdout = tokio::io::stdout();
+
+    while let Some(message) = read_message(&mut stdin).await? {
+        let response = handle_message(&message).await;
+        write_message(&mut stdout, &response).await?;
+    }
+    Ok(())
+}
+
+async fn handle_message(message: &Value) -> Value {
+    let id = message["id"].clone();
+    let result = match message["type"].as_str() {
+        Some("actions") => Ok(json!(actions::available_actions())),
+        Some("models") => list_models().await,
+        Some("action") => run_action(message).await,
+        _ => Err("Unknown message type".to_string()),
+    };
+    match result {
+        Ok(result) => json!({ "id": id, "result": result }),
+        Err(e) => json!({ "id": id, "error": e }),
+    }
+}
+
+async fn list_models() -> Result<Value, String> {
+    let api_key = cli::stored_api_key()?;
+    let models = models::models(&api_key).await?;
+    // Only ids and names, the full catalog exceeds the 1 MB message limit
+    Ok(json!(models
+        .iter()
+        .map(|model| json!({ "id": model.id, "name": model.name }))
+        .collect::<Vec<_>>()))
+}
+
+async fn run_action(message: &Value) -> Result<Value, String> {
+    let action = message["action"].as_str().ok_or("Missing action")?;
+    let text = message["text"].as_str().ok_or("Missing text")?;
+    let output = actions::run_action(ActionRequest {
+        text: text.to_string(),
+        action: action.to_string(),
+        model: cli::resolve_model(message["model"].as_str().map(str::to_string)),
+        api_key: cli::stored_api_key().unwrap_or_default(),
+        tone: message["tone"].as_str().map(str::to_string),
+        max_tokens: None,
+        image: None,
+    })
+    .await?;
+    Ok(Value::String(output.text))
+}
+
+async fn read_message(stdin: &mut (impl AsyncReadExt + Unpin)) -> Result<Option<Value>, String> {
+    let mut length = [0u8; 4];
+    match stdin.read_exact(&mut length).await {
+        Ok(_) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(format!("Failed to read stdin: {}", e)),
+    }
+    let length = u32::from_ne_bytes(length) as usize;
+    if length > MAX_INCOMING_MESSAGE {
+        return Err(format!("Message too large: {} bytes", length));
+    }
+    let mut body = vec![0; length];
+    stdin
+        .read_exact(&mut body)
+        .await
+        .map_err(|e| format!("Failed to read stdin: {}", e))?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|e| format!("Invalid message: {}", e))
+}
+
+async fn write_message(stdout: &mut (impl AsyncWriteExt + Unpin), message: &Value) -> Result<(), String> {
+    let mut body = message.to_string();
+    if body.len() > MAX_OUTGOING_MESSAGE {
+        body = json!({ "id": message["id"], "error": "Response exceeds the 1 MB native messaging limit" }).to_string();
+    }
+    stdout
+        .write_all(&(body.len() as u32).to_ne_bytes())
+        .await
+        .map_err(|e| format!("Failed to write stdout: {}", e))?;
+    stdout
+        .write_all(body.as_bytes())
+        .await
+        .map_err(|e| format!("Failed to write stdout: {}", e))?;
+    stdout.flush().await.map_err(|e| format!("Failed to write stdout: {}", e))
+}
+
+pub fn manifest(browser: Browser, extension_id: &str) -> Result<Value, String> {
+    let path = std::env::current_exe().map_err(|e| format!("Failed to locate the wraiter binary: {}", e))?;
+    let mut manifest = json!({
+        "name": HOST_NAME,
+        "description": HOST_DESCRIPTION,
+        "path": path,
+        "type": "stdio"
+    });
+    match browser {
+        Browser::Chrome | Browser::Chromium => {
+            manifest["allowed_origins"] = json!([format!("chrome-extension://{}/", extension_id)]);
+        }
+        Browser::Firefox => {
+            manifest["allowed_extensions"] = json!([extension_id]);
+        }
+    }
+    Ok(manifest)
+}
+
+// Write the manifest where the browser looks for native messaging hosts
+pub fn install_manifest(browser: Browser, extension_id: &str) -> Result<PathBuf, String> {
+    let manifest = manifest(browser, extension_id)?;
+    let path = manifest_directory(browser)?.join(format!("{}.json", HOST_NAME));
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let contents = serde_json::to_string_pretty(&manifest).map_err(|e| e.to_string())?;
+    std::fs::write(&path, contents).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    register_manifest(browser, &path)?;
+    Ok(path)
+}
+
+#[cfg(target_os = "linux")]
+fn manifest_directory(browser: Browser) -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or("Failed to locate the home directory")?;
+    Ok(match browser {
+        Browser::Chrome => home.join(".config/google-chrome/NativeMessagingHosts"),
+        Browser::Chromium => home.join(".config/chromium/NativeMessagingHosts"),
+        Browser::Firefox => home.join(".mozilla/native-messaging-hosts"),
+    })
+}
+
+#[cfg(target_os = "macos")]
+fn manifest_directory(browser: Browser) -> Result<PathBuf, String> {
+    let support = dirs::home_dir()
+        .ok_or("Failed to locate the home directory")?
+        .join("Library/Application Support");
+    Ok(match browser {
+        Browser::Chrome => support.join("Google/Chrome/NativeMessagingHosts"),
+        Browser::Chromium => support.join("Chromium/NativeMessagingHosts"),
+        Browser::Firefox => support.join("Mozilla/NativeMessagingHosts"),
+    })
+}
+
+// Windows finds manifests through the registry, so keep them next to the app data
+#[cfg(windows)]
+fn manifest_directory(browser: Browser) -> Result<PathBuf, String> {
+    let directory = dirs::data_dir()
+        .ok_or("Failed to locate the app data directory")?
+        .join(cli::APP_IDENTIFIER)
+        .join("NativeMessagingHosts");
+    Ok(match browser {
+        Browser::Chrome | Browser::Chromium => directory.join("chrome"),
+        Browser::Firefox => directory.join("firefox"),
+    })
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn manifest_directory(_browser: Browser) -> Result<PathBuf, String> {
+    Err("Installing the native messaging host is not supported on this platform".to_string())
+}
+
+#[cfg(windows)]
+fn register_manifest(browser: Browser, path: &std::path::Path) -> Result<(), String> {
+    let key = match browser {
+        Browser::Chrome => format!("HKCU\\Software\\Google\\Chrome\\NativeMessagingHosts\\{}", HOST_NAME),
+        Browser::Chromium => format!("HKCU\\Software\\Chromium\\NativeMessagingHosts\\{}", HOST_NAME),
+        Browser::Firefox => format!("HKCU\\Software\\Mozilla\\NativeMessagingHosts\\{}", HOST_NAME),
+    };
+    let status = std::process::Command::new("reg")
+        .args(["add", &key, "/ve", "/t", "REG_SZ", "/d"])
+        .arg(path)
+        .arg("/f")
+        .status()
+        .map_err(|e| format!("Failed to run reg: {}", e))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("Failed to register {} in the registry", key))
+    }
+}
+
+#[cfg(not(windows))]
+fn register_manifest(_browser: Browser, _path: &std::path::Path) -> Result<(), String> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(body: &[u8]) -> Vec<u8> {
+        let mut frame = (body.len() as u32).to_ne_bytes().to_vec();
+        frame.extend_from_slice(body);
+        frame
+    }
+
+    #[tokio::test]
+    async fn messages_are_read_by_their_length_prefix() {
+        let mut input = frame(br#"{"id":1,"type":"actions"}"#);
+        input.extend(frame(br#"{"id":2}"#));
+        let mut stdin = input.as_slice();
+        assert_eq!(read_message(&mut stdin).await.unwrap(), Some(json!({ "id": 1, "type": "actions" })));
+        assert_eq!(read_message(&mut stdin).await.unwrap(), Some(json!({ "id": 2 })));
+        // The browser closing stdin ends the session
+        assert_eq!(read_message(&mut stdin).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn broken_frames_are_errors() {
+        let mut truncated = &frame(br#"{"id":1}"#)[..8];
+        assert!(read_message(&mut truncated).await.is_err());
+        let mut invalid = frame(b"not json");
+        assert!(read_message(&mut invalid.as_slice()).await.unwrap_err().starts_with("Invalid message"));
+        invalid = ((MAX_INCOMING_MESSAGE + 1) as u32).to_ne_bytes().to_vec();
+        assert!(read_message(&mut invalid.as_slice()).await.unwrap_err().starts_with("Message too large"));
+    }
+
+    #[tokio::test]
+    async fn written_messages_round_trip() {
+        let message = json!({ "id": 3, "result": "done" });
+        let mut stdout = Vec::new();
+        write_message(&mut stdout, &message).await.unwrap();
+        assert_eq!(stdout, frame(message.to_string().as_bytes()));
+        assert_eq!(read_message(&mut stdout.as_slice()).await.unwrap(), Some(message));
+    }
+
+    #[tokio::test]
+    async fn oversized_responses_are_replaced_with_an_error() {
+        let message = json!({ "id": 4, "result": "x".repeat(MAX_OUTGOING_MESSAGE) });
+        let mut stdout = Vec::new();
+        write_message(&mut stdout, &message).await.unwrap();
+        let response = read_message(&mut stdout.as_slice()).await.unwrap().unwrap();
+        assert_eq!(response["id"], 4);
+        assert!(response["error"].as_str().unwrap().contains("1 MB"));
+    }
+}
@@ -0,0 +1,308 @@
+// Thin client for the OpenRouter REST API shared by the Tauri commands and the CLI
+use crate::error::CallError;
+use serde::{Deserialize, Serialize};
+
+const MODELS_URL: &str = "https://openrouter.ai/api/v1/models";
+const CHAT_COMPLETIONS_URL: &str = "https://openrouter.ai/api/v1/chat/completions";
+const KEY_URL: &str = "https://openrouter.ai/api/v1/key";
+const CREDITS_URL: &str = "https://openrouter.ai/api/v1/credits";
+
+// A model from the OpenRouter catalog
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Model {
+    pub id: String,
+    pub name: String,
+    // Unix timestamp in seconds of when OpenRouter added the model
+    #[serde(default)]
+    pub created: i64,
+    #[serde(default)]
+    pub description: String,
+    pub context_length: Option<u64>,
+    pub pricing: Option<Pricing>,
+    #[serde(default)]
+    pub input_modalities: Vec<String>,
+    #[serde(default)]
+    pub output_modalities: Vec<String>,
+    // Request parameters the model accepts, e.g. "temperature" or "tools"
+    #[serde(default)]
+    pub supported_parameters: Vec<String>,
+}
+
+// USD per token, and per request for models with a flat fee
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct Pricing {
+    pub prompt: f64,
+    pub completion: f64,
+    pub request: f64,
+}
+
+impl Pricing {
+    pub fn cost(&self, prompt_tokens: u64, completion_tokens: u64) -> f64 {
+        self.prompt * prompt_tokens as f64 + self.completion * completion_tokens as f64 + self.request
+    }
+
+    pub fn is_free(&self) -> bool {
+        self.prompt == 0.0 && self.completion == 0.0 && self.request == 0.0
+    }
+}
+
+impl Model {
+    // Parse an entry of the model list, None if it has no id
+    fn from_listing(model: &serde_json::Value) -> Option<Self> {
+        let id = model["id"].as_str()?.to_string();
+        let architecture = &model["architecture"];
+        let strings = |value: &serde_json::Value| -> Vec<String> {
+            value
+                .as_array()
+                .map(|values| values.iter().filter_map(|v| v.as_str().map(str::to_string)).collect())
+                .unwrap_or_default()
+        };
+        let mut input_modalities = strings(&architecture["input_modalities"]);
+        let mut output_modalities = strings(&architecture["output_modalities"]);
+        // Older listings only carry a combined "text+image->text" modality string
+        if let Some((inputs, outputs)) = architecture["modality"].as_str().and_then(|m| m.split_once("->")) {
+            if input_modalities.is_empty() {
+                input_modalities = inputs.split('+').map(str::to_string).collect();
+            }
+            if output_modalities.is_empty() {
+                output_modalities = outputs.split('+').map(str::to_string).collect();
+            }
+        }
+        Some(Self {
+            name: model["name"].as_str().unwrap_or(&id).to_string(),
+            created: model["created"].as_i64().unwrap_or_default(),
+            description: model["description"].as_str().unwrap_or_default().to_string(),
+            context_length: model["context_length"].as_u64(),
+            pricing: parse_pricing(&model["pricing"]),
+            input_modalities,
+            output_modalities,
+            supported_parameters: strings(&model["supported_parameters"]),
+            id,
+        })
+    }
+
+    pub fn accepts_input(&self, modality: &str) -> bool {
+        self.input_modalities.iter().any(|m| m.eq_ignore_ascii_case(modality))
+    }
+
+    pub fn is_free(&self) -> bool {
+        self.pricing.map(|pricing| pricing.is_free()).unwrap_or(false)
+    }
+}
+
+pub enum ModelList {
+    Models { models: Vec<Model>, etag: Option<String> },
+    // The catalog hasn't changed since the response with the given ETag
+    NotModified,
+}
+
+// Download the model list, or only check it is unchanged when an ETag from an earlier response is given
+pub async fn fetch_models(api_key: &str, etag: Option<&str>) -> Result<ModelList, CallError> {
+    let client = reqwest::Client::new();
+    let mut request = client
+        .get(MODELS_URL)
+        .header("Authorization", format!("Bearer {}", api_key))
+        .header("Content-Type", "application/json");
+    if let Some(etag) = etag {
+        request = request.header("If-None-Match", etag);
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("Failed to connect to OpenRouter API: {}", e))?;
+    let status = response.status();
+    if status == reqwest::StatusCode::NOT_MODIFIED {
+        return Ok(ModelList::NotModified);
+    }
+    if !status.is_success() {
+        return Err(response_error(response).await);
+    }
+    let etag = response
+        .headers()
+        .get(reqwest::header::ETAG)
+        .and_then(|etag| etag.to_str().ok())
+        .map(str::to_string);
+    let body = response
+        .json::<serde_json::Value>()
+        .await
+        .map_err(|e| format!("Failed to parse models response: {}", e))?;
+    let models = body["data"]
+        .as_array()
+        .map(|models| models.iter().filter_map(Model::from_listing).collect())
+        .unwrap_or_default();
+    Ok(ModelList::Models { models, etag })
+}
+
+// Prices are given as decimal strings, e.g. "0.0000001"
+fn parse_pricing(pricing: &serde_json::Value) -> Option<Pricing> {
+    let price = |key: &str| {
+        let value = &pricing[key];
+        value.as_str().and_then(|price| price.parse::<f64>().ok()).or_else(|| value.as_f64())
+    };
+    Some(Pricing {
+        prompt: price("prompt")?,
+        completion: price("completion")?,
+        request: price("request").unwrap_or(0.0),
+    })
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RateLimit {
+    pub requests: u64,
+    pub interval: String,
+}
+
+// What OpenRouter reports about a key and the account it belongs to
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct KeyStatus {
+    pub valid: bool,
+    // Why the key was rejected, when it isn't valid
+    pub error: Option<String>,
+    pub label: Option<String>,
+    pub is_free_tier: bool,
+    // USD spent with this key
+    pub usage: f64,
+    // Spending cap set on the key, None when unlimited
+    pub limit: Option<f64>,
+    pub limit_remaining: Option<f64>,
+    pub rate_limit: Option<RateLimit>,
+    // Account balance: credits purchased minus credits used
+    pub credits_remaining: Option<f64>,
+}
+
+// Validate the key and read its limits and the account's credit balance
+pub async fn fetch_key_status(api_key: &str) -> Result<KeyStatus, String> {
+    let client = reqwest::Client::new();
+    let response = client
+        .get(KEY_URL)
+        .header("Authorization", format!("Bearer {}", api_key))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to connect to OpenRouter API: {}", e))?;
+
+    let status = response.status();
+    if status == 401 || status == 403 {
+        return Ok(KeyStatus {
+            valid: false,
+            error: Some(response_error(response).await.to_string()),
+            ..KeyStatus::default()
+        });
+    }
+    if !status.is_success() {
+        return Err(response_error(response).await.to_string());
+    }
+    let body = response
+        .json::<serde_json::Value>()
+        .await
+        .map_err(|e| format!("Failed to parse key info: {}", e))?;
+    let data = &body["data"];
+
+    let rate_limit = match (data["rate_limit"]["requests"].as_u64(), data["rate_limit"]["interval"].as_str()) {
+        (Some(requests), Some(interval)) => Some(RateLimit {
+            requests,
+            interval: interval.to_string(),
+        }),
+        _ => None,
+    };
+    Ok(KeyStatus {
+        valid: true,
+        error: None,
+        label: data["label"].as_str().map(str::to_string),
+        is_free_tier: data["is_free_tier"].as_bool().unwrap_or(false),
+        usage: data["usage"].as_f64().unwrap_or(0.0),
+        limit: data["limit"].as_f64(),
+        limit_remaining: data["limit_remaining"].as_f64(),
+        rate_limit,
+        // The balance is informational, so a failure here doesn't fail the check
+        credits_remaining: fetch_credits_remaining(&client, api_key).await,
+    })
+}
+
+async fn fetch_credits_remaining(client: &reqwest::Client, api_key: &str) -> Option<f64> {
+    let response = client
+        .get(CREDITS_URL)
+        .header("Authorization", format!("Bearer {}", api_key))
+        .send()
+        .await
+        .ok()?;
+    if !response.status().is_success() {
+        return None;
+    }
+    let body = response.json::<serde_json::Value>().await.ok()?;
+    let data = &body["data"];
+    Some(data["total_credits"].as_f64()? - data["total_usage"].as_f64()?)
+}
+
+// Send a chat completion request and return the raw response body
+pub async fn chat_completion(api_key: &str, request_body: &serde_json::Value) -> Result<serde_json::Value, CallError> {
+    let client = reqwest::Client::new();
+    let response = client
+        .post(CHAT_COMPLETIONS_URL)
+        .header("Authorization", format!("Bearer {}", api_key))
+        .header("Content-Type", "application/json")
+        .json(request_body)
+        .send()
+        .await;
+
+    match response {
+        Ok(resp) => {
+            if resp.status().is_success() {
+                resp.json::<serde_json::Value>()
+                    .await
+                    .map_err(|e| CallError::Other(format!("Failed to parse AI response: {}", e)))
+            } else {
+                Err(response_error(resp).await)
+            }
+        }
+        Err(e) => Err(CallError::Other(format!("Failed to connect to OpenRouter API: {}", e)))
+    }
+}
+
+// Send a streaming chat completion request and return the response for the caller to forward
+pub async fn chat_completion_stream(api_key: &str, request_body: &serde_json::Value) -> Result<reqwest::Response, CallError> {
+    let client = reqwest::Client::new();
+    let response = client
+        .post(CHAT_COMPLETIONS_URL)
+        .header("Authorization", format!("Bearer {}", api_key))
+        .header("Content-Type", "application/json")
+        .json(request_body)
+        .send()
+        .await;
+
+    match response {
+        Ok(resp) => {
+            if resp.status().is_success() {
+                Ok(resp)
+            } else {
+                Err(response_error(resp).await)
+            }
+        }
+        Err(e) => Err(CallError::Other(format!("Failed to connect to OpenRouter API: {}", e)))
+    }
+}
+
+async fn response_error(resp: reqwest::Response) -> CallError {
+    let status = resp.status();
+    let message = match resp.text().await {
+        Ok(error_text) => {
+            // Parse the error response to provide better error messages
+            if status == 401 {
+                format!("Authentication failed (401 Unauthorized): {}", error_text)
+            } else if status == 403 {
+                format!("Access forbidden (403 Forbidden): {}", error_text)
+            } else if status == 402 {
+                format!("Insufficient credits (402 Payment Required): {}", error_text)
+            } else if status == 429 {
+                format!("Rate limit exceeded (429 Too Many Requests): {}", error_text)
+            } else {
+                format!("API request failed ({}): {}", status, error_text)
+            }
+        },
+        Err(_) => format!("API request failed with status: {}", status)
+    };
+    CallError::Provider {
+        status: status.as_u16(),
+        message,
+    }
+}
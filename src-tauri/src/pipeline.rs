@@ -0,0 +1,120 @@
+// Pipelines chain several actions, feeding each step's output into the next
+use crate::actions::{self, ActionRequest};
+use crate::usage::Usage;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineStep {
+    pub action: String,
+    #[serde(default)]
+    pub tone: Option<String>,
+    // Overrides the pipeline's model for this step only
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Pipeline {
+    pub id: String,
+    pub name: String,
+    pub steps: Vec<PipelineStep>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StepResult {
+    pub action: String,
+    pub model: String,
+    pub output: Option<String>,
+    pub error: Option<String>,
+    pub usage: Option<Usage>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PipelineResult {
+    // Every step that ran, including the one that failed
+    pub steps: Vec<StepResult>,
+    // Output of the last step, or None if a step failed
+    pub output: Option<String>,
+    pub error: Option<String>,
+}
+
+pub fn validate(pipelines: &[Pipeline]) -> Result<(), String> {
+    for (index, pipeline) in pipelines.iter().enumerate() {
+        if pipeline.id.is_empty() {
+            return Err(format!("Pipeline {} has no id", index + 1));
+        }
+        if pipelines[..index].iter().any(|other| other.id == pipeline.id) {
+            return Err(format!("Duplicate pipeline id: {}", pipeline.id));
+        }
+        if pipeline.steps.is_empty() {
+            return Err(format!("Pipeline '{}' has no steps", pipeline.name));
+        }
+        for step in &pipeline.steps {
+            if !actions::is_known_action(&step.action) {
+                return Err(format!("Pipeline '{}' uses an unknown action: {}", pipeline.name, step.action));
+            }
+        }
+    }
+    Ok(())
+}
+
+// Run the steps in order, stopping at the first failing step
+pub async fn run(
+    steps: &[PipelineStep],
+    text: String,
+    model: &str,
+    api_key: &str,
+    max_tokens: Option<u32>,
+) -> PipelineResult {
+    let mut results = Vec::with_capacity(steps.len());
+    let mut current = text;
+
+    for (index, step) in steps.iter().enumerate() {
+        let step_model = step.model.clone().filter(|m| !m.is_empty()).unwrap_or_else(|| model.to_string());
+        let result = actions::run_action(ActionRequest {
+            text: current.clone(),
+            action: step.action.clone(),
+            model: step_model.clone(),
+            api_key: api_key.to_string(),
+            tone: step.tone.clone(),
+            max_tokens: step.max_tokens.or(max_tokens),
+            image: None,
+        })
+        .await;
+
+        match result {
+            Ok(output) => {
+                results.push(StepResult {
+                    action: step.action.clone(),
+                    model: step_model,
+                    output: Some(output.text.clone()),
+                    error: None,
+                    usage: output.usage,
+                });
+                current = output.text;
+            }
+            Err(e) => {
+                results.push(StepResult {
+                    action: step.action.clone(),
+                    model: step_model,
+                    output: None,
+                    error: Some(e.to_string()),
+                    usage: None,
+                });
+                return PipelineResult {
+                    steps: results,
+                    output: None,
+                    error: Some(format!("Step {} ({}) failed: {}", index + 1, step.action, e)),
+                };
+            }
+        }
+    }
+
+    PipelineResult {
+        steps: results,
+        output: Some(current),
+        error: None,
+    }
+}
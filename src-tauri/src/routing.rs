@@ -0,0 +1,162 @@
+// OpenRouter provider routing preferences, added as the `provider` object of every
+// chat completion request the app sends or proxies
+use serde::{Deserialize, Serialize};
+use std::sync::RwLock;
+
+// Quantization levels OpenRouter can filter providers by
+const QUANTIZATIONS: &[&str] = &["int4", "int8", "fp4", "fp6", "fp8", "fp16", "bf16", "fp32", "unknown"];
+
+// None leaves routing to OpenRouter
+static PREFERENCES: RwLock<Option<ProviderPreferences>> = RwLock::new(None);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DataCollection {
+    Allow,
+    // Only use providers that don't store or train on prompts
+    Deny,
+}
+
+// Unset values keep OpenRouter's defaults
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProviderPreferences {
+    // Provider names to try first, in this order, e.g. "anthropic" or "deepinfra"
+    #[serde(default)]
+    pub order: Vec<String>,
+    // Whether other providers may serve the request when the preferred ones are unavailable
+    #[serde(default)]
+    pub allow_fallbacks: Option<bool>,
+    #[serde(default)]
+    pub data_collection: Option<DataCollection>,
+    // Only use providers that support every parameter in the request
+    #[serde(default)]
+    pub require_parameters: Option<bool>,
+    #[serde(default)]
+    pub quantizations: Vec<String>,
+}
+
+impl ProviderPreferences {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.order.iter().any(|provider| provider.trim().is_empty()) {
+            return Err("Provider names must not be empty".to_string());
+        }
+        if let Some(quantization) = self
+            .quantizations
+            .iter()
+            .find(|quantization| !QUANTIZATIONS.contains(&quantization.as_str()))
+        {
+            return Err(format!(
+                "Unknown quantization '{}', expected one of {}",
+                quantization,
+                QUANTIZATIONS.join(", ")
+            ));
+        }
+        Ok(())
+    }
+
+    // The preferences that are set, as OpenRouter's `provider` object
+    fn body(&self) -> serde_json::Map<String, serde_json::Value> {
+        let mut provider = serde_json::Map::new();
+        if !self.order.is_empty() {
+            provider.insert("order".to_string(), serde_json::json!(self.order));
+        }
+        if let Some(allow_fallbacks) = self.allow_fallbacks {
+            provider.insert("allow_fallbacks".to_string(), serde_json::json!(allow_fallbacks));
+        }
+        if let Some(data_collection) = self.data_collection {
+            provider.insert("data_collection".to_string(), serde_json::json!(data_collection));
+        }
+        if let Some(require_parameters) = self.require_parameters {
+            provider.insert("require_parameters".to_string(), serde_json::json!(require_parameters));
+        }
+        if !self.quantizations.is_empty() {
+            provider.insert("quantizations".to_string(), serde_json::json!(self.quantizations));
+        }
+        provider
+    }
+}
+
+pub fn set_preferences(preferences: Option<ProviderPreferences>) {
+    if let Ok(mut current) = PREFERENCES.write() {
+        *current = preferences;
+    }
+}
+
+// Merge the preferences into a request body. They replace the same keys of a `provider`
+// object the caller sent, so a policy such as denying data collection can't be bypassed.
+pub fn apply(body: &mut serde_json::Value) {
+    if let Some(preferences) = PREFERENCES.read().ok().and_then(|preferences| preferences.clone()) {
+        merge(&preferences, body);
+    }
+}
+
+fn merge(preferences: &ProviderPreferences, body: &mut serde_json::Value) {
+    let preferences = preferences.body();
+    if preferences.is_empty() {
+        return;
+    }
+    let Some(body) = body.as_object_mut() else { return };
+    let provider = body
+        .entry("provider")
+        .or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()));
+    match provider.as_object_mut() {
+        Some(provider) => provider.extend(preferences),
+        None => *provider = serde_json::Value::Object(preferences),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn deny() -> ProviderPreferences {
+        ProviderPreferences {
+            order: vec!["deepinfra".to_string()],
+            data_collection: Some(DataCollection::Deny),
+            ..ProviderPreferences::default()
+        }
+    }
+
+    #[test]
+    fn preferences_are_added_as_the_provider_object() {
+        let mut body = json!({ "model": "a/x" });
+        merge(&deny(), &mut body);
+        assert_eq!(body["provider"], json!({ "order": ["deepinfra"], "data_collection": "deny" }));
+    }
+
+    #[test]
+    fn preferences_replace_the_callers_keys_only() {
+        let mut body = json!({ "provider": { "data_collection": "allow", "sort": "price" } });
+        merge(&deny(), &mut body);
+        assert_eq!(
+            body["provider"],
+            json!({ "order": ["deepinfra"], "data_collection": "deny", "sort": "price" })
+        );
+
+        let mut body = json!({ "provider": "invalid" });
+        merge(&deny(), &mut body);
+        assert_eq!(body["provider"]["data_collection"], "deny");
+    }
+
+    #[test]
+    fn unset_preferences_leave_the_body_alone() {
+        let mut body = json!({ "provider": { "sort": "price" } });
+        merge(&ProviderPreferences::default(), &mut body);
+        assert_eq!(body, json!({ "provider": { "sort": "price" } }));
+    }
+
+    #[test]
+    fn validate_rejects_unknown_quantizations_and_empty_providers() {
+        let mut preferences = ProviderPreferences {
+            quantizations: vec!["fp8".to_string()],
+            ..ProviderPreferences::default()
+        };
+        assert!(preferences.validate().is_ok());
+        preferences.quantizations.push("fp7".to_string());
+        assert!(preferences.validate().unwrap_err().contains("'fp7'"));
+        preferences.quantizations.clear();
+        preferences.order.push(" ".to_string());
+        assert!(preferences.validate().is_err());
+    }
+}
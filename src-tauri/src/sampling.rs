@@ -0,0 +1,329 @@
+// Sampling and reasoning parameters sent with model calls. The profile's defaults and per-action
+// settings override the built-in defaults, and the result is checked against what the model supports.
+use crate::error::CallError;
+use crate::openrouter::Model;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+
+const DEFAULT_TEMPERATURE: f64 = 0.7;
+// Corrections should stay close to the text instead of varying between runs
+const PROOFREAD_TEMPERATURE: f64 = 0.2;
+// The most stop sequences OpenAI-compatible providers accept
+const MAX_STOP_SEQUENCES: usize = 4;
+
+// None until the profile has saved sampling settings
+static SAMPLING: RwLock<Option<SamplingSettings>> = RwLock::new(None);
+
+// Unset values fall back to the next layer
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SamplingParams {
+    #[serde(default)]
+    pub temperature: Option<f64>,
+    #[serde(default)]
+    pub top_p: Option<f64>,
+    #[serde(default)]
+    pub frequency_penalty: Option<f64>,
+    #[serde(default)]
+    pub presence_penalty: Option<f64>,
+    #[serde(default)]
+    pub stop: Vec<String>,
+    #[serde(default)]
+    pub seed: Option<i64>,
+    #[serde(default)]
+    pub reasoning: Option<ReasoningSettings>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReasoningEffort {
+    Low,
+    Medium,
+    High,
+}
+
+impl ReasoningEffort {
+    // Roughly the share of max_tokens OpenRouter lets the model spend on reasoning
+    fn share(self) -> f64 {
+        match self {
+            ReasoningEffort::Low => 0.2,
+            ReasoningEffort::Medium => 0.5,
+            ReasoningEffort::High => 0.8,
+        }
+    }
+}
+
+// Sent as OpenRouter's `reasoning` option. Models take either an effort or a token budget.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReasoningSettings {
+    #[serde(default)]
+    pub effort: Option<ReasoningEffort>,
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
+    // Reason without returning the reasoning in the response
+    #[serde(default)]
+    pub exclude: bool,
+}
+
+impl ReasoningSettings {
+    fn body(&self) -> serde_json::Value {
+        let mut reasoning = serde_json::Map::new();
+        if let Some(effort) = self.effort {
+            reasoning.insert("effort".to_string(), serde_json::json!(effort));
+        }
+        if let Some(max_tokens) = self.max_tokens {
+            reasoning.insert("max_tokens".to_string(), serde_json::json!(max_tokens));
+        }
+        reasoning.insert("exclude".to_string(), serde_json::json!(self.exclude));
+        serde_json::Value::Object(reasoning)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SamplingSettings {
+    // Applied to every action
+    #[serde(default)]
+    pub defaults: SamplingParams,
+    // By action id, overriding the defaults
+    #[serde(default)]
+    pub actions: BTreeMap<String, SamplingParams>,
+}
+
+impl SamplingParams {
+    fn overlay(mut self, other: &SamplingParams) -> Self {
+        self.temperature = other.temperature.or(self.temperature);
+        self.top_p = other.top_p.or(self.top_p);
+        self.frequency_penalty = other.frequency_penalty.or(self.frequency_penalty);
+        self.presence_penalty = other.presence_penalty.or(self.presence_penalty);
+        if !other.stop.is_empty() {
+            self.stop = other.stop.clone();
+        }
+        self.seed = other.seed.or(self.seed);
+        self.reasoning = other.reasoning.clone().or(self.reasoning);
+        self
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let ranges = [
+            ("temperature", self.temperature, 0.0, 2.0),
+            ("top_p", self.top_p, 0.0, 1.0),
+            ("frequency_penalty", self.frequency_penalty, -2.0, 2.0),
+            ("presence_penalty", self.presence_penalty, -2.0, 2.0),
+        ];
+        for (name, value, min, max) in ranges {
+            if let Some(value) = value {
+                if !(min..=max).contains(&value) {
+                    return Err(format!("{} must be between {} and {}", name, min, max));
+                }
+            }
+        }
+        if self.stop.len() > MAX_STOP_SEQUENCES {
+            return Err(format!("At most {} stop sequences are allowed", MAX_STOP_SEQUENCES));
+        }
+        if self.stop.iter().any(|stop| stop.is_empty()) {
+            return Err("Stop sequences must not be empty".to_string());
+        }
+        if let Some(reasoning) = &self.reasoning {
+            if reasoning.effort.is_some() && reasoning.max_tokens.is_some() {
+                return Err("Set either a reasoning effort or a reasoning token budget, not both".to_string());
+            }
+            if reasoning.max_tokens == Some(0) {
+                return Err("The reasoning token budget must be at least 1".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    // The values that are set, keyed by their request body and `supported_parameters` names
+    fn parameters(&self) -> serde_json::Map<String, serde_json::Value> {
+        let mut parameters = serde_json::Map::new();
+        let numbers = [
+            ("temperature", self.temperature),
+            ("top_p", self.top_p),
+            ("frequency_penalty", self.frequency_penalty),
+            ("presence_penalty", self.presence_penalty),
+        ];
+        for (name, value) in numbers {
+            if let Some(value) = value {
+                parameters.insert(name.to_string(), serde_json::json!(value));
+            }
+        }
+        if !self.stop.is_empty() {
+            parameters.insert("stop".to_string(), serde_json::json!(self.stop));
+        }
+        if let Some(seed) = self.seed {
+            parameters.insert("seed".to_string(), serde_json::json!(seed));
+        }
+        if let Some(reasoning) = &self.reasoning {
+            parameters.insert("reasoning".to_string(), reasoning.body());
+        }
+        parameters
+    }
+}
+
+impl SamplingSettings {
+    // The action's settings over the defaults
+    fn for_action(&self, action: &str) -> SamplingParams {
+        self.defaults
+            .clone()
+            .overlay(&self.actions.get(action).cloned().unwrap_or_default())
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        self.defaults.validate().map_err(|e| format!("Default sampling settings: {}", e))?;
+        for (action, params) in &self.actions {
+            params
+                .validate()
+                .map_err(|e| format!("Sampling settings for '{}': {}", action, e))?;
+        }
+        Ok(())
+    }
+}
+
+pub fn set_sampling(settings: Option<SamplingSettings>) {
+    if let Ok(mut current) = SAMPLING.write() {
+        *current = settings;
+    }
+}
+
+fn built_in(action: &str) -> SamplingParams {
+    SamplingParams {
+        temperature: Some(match action {
+            "proofread" => PROOFREAD_TEMPERATURE,
+            _ => DEFAULT_TEMPERATURE,
+        }),
+        ..SamplingParams::default()
+    }
+}
+
+// Parameters to add to the request body. A configured parameter the model doesn't support
+// is an error; built-in defaults it doesn't support are left out.
+pub fn parameters_for(
+    action: &str,
+    model: Option<&Model>,
+) -> Result<serde_json::Map<String, serde_json::Value>, CallError> {
+    supported_parameters(built_in(action), configured(action), model)
+}
+
+fn supported_parameters(
+    built_in: SamplingParams,
+    configured: SamplingParams,
+    model: Option<&Model>,
+) -> Result<serde_json::Map<String, serde_json::Value>, CallError> {
+    let configured = configured.parameters();
+    let mut parameters = built_in.parameters();
+
+    // Models without a list of supported parameters are sent everything
+    if let Some(model) = model.filter(|model| !model.supported_parameters.is_empty()) {
+        let supported = |name: &String| model.supported_parameters.contains(name);
+        if let Some(name) = configured.keys().find(|name| !supported(name)) {
+            return Err(CallError::InvalidRequest(format!(
+                "Model {} does not support the {} parameter",
+                model.id, name
+            )));
+        }
+        parameters.retain(|name, _| supported(name));
+    }
+    parameters.extend(configured);
+    Ok(parameters)
+}
+
+// Reasoning tokens count against max_tokens, so the limit is raised to keep `answer_tokens`
+// for the answer itself
+pub fn max_tokens_for(action: &str, answer_tokens: u32) -> u32 {
+    match configured(action).reasoning {
+        Some(ReasoningSettings { max_tokens: Some(budget), .. }) => answer_tokens.saturating_add(budget),
+        Some(ReasoningSettings { effort: Some(effort), .. }) => {
+            (f64::from(answer_tokens) / (1.0 - effort.share())).ceil() as u32
+        }
+        _ => answer_tokens,
+    }
+}
+
+fn configured(action: &str) -> SamplingParams {
+    SAMPLING
+        .read()
+        .ok()
+        .and_then(|settings| settings.as_ref().map(|settings| settings.for_action(action)))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model(supported_parameters: &[&str]) -> Model {
+        serde_json::from_value(serde_json::json!({
+            "id": "a/x",
+            "name": "X",
+            "context_length": null,
+            "pricing": null,
+            "supported_parameters": supported_parameters,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn action_settings_override_the_defaults() {
+        let settings: SamplingSettings = serde_json::from_value(serde_json::json!({
+            "defaults": { "temperature": 0.5, "top_p": 0.9, "stop": ["END"] },
+            "actions": { "draft": { "temperature": 1.2, "seed": 7 } }
+        }))
+        .unwrap();
+        let draft = settings.for_action("draft");
+        assert_eq!(draft.temperature, Some(1.2));
+        assert_eq!(draft.top_p, Some(0.9));
+        assert_eq!(draft.stop, ["END"]);
+        assert_eq!(draft.seed, Some(7));
+        assert_eq!(settings.for_action("proofread").temperature, Some(0.5));
+
+        // The configured value replaces the built-in default
+        let parameters = supported_parameters(built_in("proofread"), settings.for_action("proofread"), None).unwrap();
+        assert_eq!(parameters["temperature"], 0.5);
+        let parameters = supported_parameters(built_in("proofread"), SamplingParams::default(), None).unwrap();
+        assert_eq!(parameters["temperature"], PROOFREAD_TEMPERATURE);
+    }
+
+    #[test]
+    fn invalid_settings_are_rejected() {
+        let invalid = [
+            serde_json::json!({ "temperature": 2.5 }),
+            serde_json::json!({ "top_p": -0.1 }),
+            serde_json::json!({ "presence_penalty": 3.0 }),
+            serde_json::json!({ "stop": ["a", "b", "c", "d", "e"] }),
+            serde_json::json!({ "stop": [""] }),
+            serde_json::json!({ "reasoning": { "effort": "low", "max_tokens": 100 } }),
+            serde_json::json!({ "reasoning": { "max_tokens": 0 } }),
+        ];
+        for params in invalid {
+            let params: SamplingParams = serde_json::from_value(params.clone()).unwrap();
+            assert!(params.validate().is_err(), "{:?}", params);
+        }
+        let settings: SamplingSettings = serde_json::from_value(serde_json::json!({
+            "actions": { "draft": { "temperature": 9.0 } }
+        }))
+        .unwrap();
+        assert!(settings.validate().unwrap_err().contains("'draft'"));
+        assert!(SamplingSettings::default().validate().is_ok());
+    }
+
+    #[test]
+    fn parameters_are_checked_against_the_model() {
+        let configured = SamplingParams {
+            seed: Some(1),
+            ..SamplingParams::default()
+        };
+        // Built-in defaults the model doesn't support are left out
+        let parameters = supported_parameters(built_in("draft"), configured.clone(), Some(&model(&["seed"]))).unwrap();
+        assert!(!parameters.contains_key("temperature"));
+        assert_eq!(parameters["seed"], 1);
+        // Configured ones are an error
+        let error = supported_parameters(built_in("draft"), configured.clone(), Some(&model(&["temperature"])));
+        assert!(matches!(error, Err(CallError::InvalidRequest(_))));
+        // Models that don't list their parameters, or aren't in the catalog, get everything
+        for model in [Some(model(&[])), None] {
+            let parameters = supported_parameters(built_in("draft"), configured.clone(), model.as_ref()).unwrap();
+            assert!(parameters.contains_key("temperature") && parameters.contains_key("seed"));
+        }
+    }
+}
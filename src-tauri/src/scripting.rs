@@ -0,0 +1,219 @@
+// Rhai scripts attached to actions that transform the input before it is sent
+// and the output after the action returns
+use rhai::module_resolvers::DummyModuleResolver;
+use rhai::{Dynamic, Engine, Map, Scope, AST};
+use serde::{Deserialize, Serialize};
+use std::sync::{LazyLock, RwLock};
+
+// Keep runaway scripts from hanging an action or exhausting memory
+const MAX_OPERATIONS: u64 = 1_000_000;
+const MAX_STRING_SIZE: usize = 4 * 1024 * 1024;
+const MAX_COLLECTION_SIZE: usize = 10_000;
+const MAX_CALL_LEVELS: usize = 32;
+
+static ENGINE: LazyLock<Engine> = LazyLock::new(engine);
+// Pre- and post-processing scripts, looked up by action id and compiled once when they are set
+static ACTION_SCRIPTS: RwLock<Vec<CompiledScript>> = RwLock::new(Vec::new());
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionScript {
+    pub action: String,
+    // Runs on the input text before the action
+    #[serde(default)]
+    pub pre: Option<String>,
+    // Runs on the action's output
+    #[serde(default)]
+    pub post: Option<String>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    Pre,
+    Post,
+}
+
+impl Stage {
+    fn label(self) -> &'static str {
+        match self {
+            Stage::Pre => "Pre-processing",
+            Stage::Post => "Post-processing",
+        }
+    }
+}
+
+// A script that fails to compile keeps its error, reported whenever the action runs
+struct CompiledScript {
+    action: String,
+    pre: Option<Result<AST, String>>,
+    post: Option<Result<AST, String>>,
+}
+
+// What a script can see besides the text it transforms
+pub struct ScriptContext<'a> {
+    pub action: &'a str,
+    pub model: &'a str,
+    pub tone: Option<&'a str>,
+    pub max_tokens: Option<u32>,
+    // The text the action was started with, also available to post scripts
+    pub input: &'a str,
+}
+
+pub fn set_action_scripts(scripts: Vec<ActionScript>) {
+    let compiled = scripts
+        .into_iter()
+        .map(|script| CompiledScript {
+            pre: compile(Stage::Pre, &script.action, script.pre.as_deref()),
+            post: compile(Stage::Post, &script.action, script.post.as_deref()),
+            action: script.action,
+        })
+        .collect();
+    if let Ok(mut current) = ACTION_SCRIPTS.write() {
+        *current = compiled;
+    }
+}
+
+// None for a missing or blank script
+fn compile(stage: Stage, action: &str, source: Option<&str>) -> Option<Result<AST, String>> {
+    let source = source.filter(|source| !source.trim().is_empty())?;
+    Some(
+        ENGINE
+            .compile(source)
+            .map_err(|e| format!("{} script for {} is invalid: {}", stage.label(), action, e)),
+    )
+}
+
+pub fn validate(scripts: &[ActionScript]) -> Result<(), String> {
+    for (index, script) in scripts.iter().enumerate() {
+        if script.action.is_empty() {
+            return Err(format!("Script {} has no action", index + 1));
+        }
+        if scripts[..index].iter().any(|other| other.action == script.action) {
+            return Err(format!("Duplicate scripts for action: {}", script.action));
+        }
+        for (stage, source) in [(Stage::Pre, &script.pre), (Stage::Post, &script.post)] {
+            compile(stage, &script.action, source.as_deref()).transpose()?;
+        }
+    }
+    Ok(())
+}
+
+// Run the action's script for the stage, returning the text unchanged if there is none
+pub fn apply(stage: Stage, text: String, context: &ScriptContext) -> Result<String, String> {
+    let ast = ACTION_SCRIPTS.read().ok().and_then(|scripts| {
+        scripts
+            .iter()
+            .find(|script| script.action == context.action)
+            .and_then(|script| match stage {
+                Stage::Pre => script.pre.clone(),
+                Stage::Post => script.post.clone(),
+            })
+    });
+    let Some(ast) = ast.transpose()? else {
+        return Ok(text);
+    };
+    let label = stage.label();
+
+    let mut settings = Map::new();
+    settings.insert(
+        "tone".into(),
+        context.tone.map(|tone| Dynamic::from(tone.to_string())).unwrap_or(Dynamic::UNIT),
+    );
+    settings.insert(
+        "max_tokens".into(),
+        context.max_tokens.map(|n| Dynamic::from(n as i64)).unwrap_or(Dynamic::UNIT),
+    );
+
+    let mut scope = Scope::new();
+    scope.push("text", text);
+    scope.push_constant("input", context.input.to_string());
+    scope.push_constant("action", context.action.to_string());
+    scope.push_constant("model", context.model.to_string());
+    scope.push_constant("settings", settings);
+
+    let result: Dynamic = ENGINE
+        .eval_ast_with_scope(&mut scope, &ast)
+        .map_err(|e| format!("{} script for {} failed: {}", label, context.action, e))?;
+
+    // A script either evaluates to the new text or assigns it to `text`
+    if result.is_string() {
+        return Ok(result.into_string().unwrap_or_default());
+    }
+    scope
+        .get_value::<String>("text")
+        .ok_or_else(|| format!("{} script for {} must leave a string in `text`", label, context.action))
+}
+
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    // Scripts must not read or evaluate other files through `import`
+    engine.set_module_resolver(DummyModuleResolver::new());
+    engine
+        .set_max_operations(MAX_OPERATIONS)
+        .set_max_string_size(MAX_STRING_SIZE)
+        .set_max_array_size(MAX_COLLECTION_SIZE)
+        .set_max_map_size(MAX_COLLECTION_SIZE)
+        .set_max_call_levels(MAX_CALL_LEVELS);
+    // stdout carries the MCP, LSP and native messaging protocols, so script output goes to stderr
+    engine.on_print(|message| eprintln!("[script] {}", message));
+    engine.on_debug(|message, _, _| eprintln!("[script] {}", message));
+    engine
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(action: &str) -> ScriptContext<'_> {
+        ScriptContext {
+            action,
+            model: "test/model",
+            tone: None,
+            max_tokens: None,
+            input: "input",
+        }
+    }
+
+    // Tests share the script registry, so they take turns setting it
+    static REGISTRY: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+    fn with_script<T>(action: &str, pre: &str, run: impl FnOnce() -> T) -> T {
+        let _guard = REGISTRY.lock().unwrap_or_else(|e| e.into_inner());
+        set_action_scripts(vec![ActionScript {
+            action: action.to_string(),
+            pre: Some(pre.to_string()),
+            post: None,
+        }]);
+        run()
+    }
+
+    #[test]
+    fn applies_the_stage_script() {
+        with_script("upper", "text.to_upper()", || {
+            assert_eq!(apply(Stage::Pre, "hello".to_string(), &context("upper")).unwrap(), "HELLO");
+            assert_eq!(apply(Stage::Post, "hello".to_string(), &context("upper")).unwrap(), "hello");
+        });
+    }
+
+    #[test]
+    fn imports_are_not_resolved() {
+        let dir = std::env::temp_dir().join(format!("wraiter-script-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("module.rhai"), "export const secret = \"leaked\";").unwrap();
+        let path = dir.join("module").to_string_lossy().replace('\\', "/");
+
+        let script = format!("import \"{}\" as m; m::secret", path);
+        let result = with_script("import", &script, || apply(Stage::Pre, "text".to_string(), &context("import")));
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(result.is_err(), "import was resolved: {:?}", result);
+    }
+
+    #[test]
+    fn rejects_invalid_scripts() {
+        let scripts = [ActionScript {
+            action: "broken".to_string(),
+            pre: Some("let = ;".to_string()),
+            post: None,
+        }];
+        assert!(validate(&scripts).is_err());
+    }
+}
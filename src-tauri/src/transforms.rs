@@ -0,0 +1,659 @@
+// Local text transforms that run entirely in Rust, without a model or network access
+pub const WRAP_WIDTH: usize = 80;
+
+pub struct Transform {
+    pub id: &'static str,
+    pub title: &'static str,
+    pub description: &'static str,
+    pub apply: fn(&str) -> Result<String, String>,
+}
+
+pub const TRANSFORMS: &[Transform] = &[
+    Transform {
+        id: "title-case",
+        title: "Title Case",
+        description: "Capitalize each word, keeping short words such as \"of\" lowercase",
+        apply: title_case,
+    },
+    Transform {
+        id: "sentence-case",
+        title: "Sentence case",
+        description: "Capitalize the first word of each sentence",
+        apply: sentence_case,
+    },
+    Transform {
+        id: "snake-case",
+        title: "snake_case",
+        description: "Convert each line to snake_case",
+        apply: snake_case,
+    },
+    Transform {
+        id: "kebab-case",
+        title: "kebab-case",
+        description: "Convert each line to kebab-case",
+        apply: kebab_case,
+    },
+    Transform {
+        id: "sort-lines",
+        title: "Sort Lines",
+        description: "Sort lines alphabetically, ignoring case",
+        apply: sort_lines,
+    },
+    Transform {
+        id: "dedupe-lines",
+        title: "Remove Duplicate Lines",
+        description: "Keep only the first occurrence of each line",
+        apply: dedupe_lines,
+    },
+    Transform {
+        id: "trim-whitespace",
+        title: "Trim Whitespace",
+        description: "Remove trailing spaces, repeated blank lines and surrounding whitespace",
+        apply: trim_whitespace,
+    },
+    Transform {
+        id: "normalize-quotes",
+        title: "Straighten Quotes",
+        description: "Replace curly quotes and apostrophes with straight ones",
+        apply: normalize_quotes,
+    },
+    Transform {
+        id: "wrap-lines",
+        title: "Wrap Lines",
+        description: "Re-wrap paragraphs to 80 columns",
+        apply: wrap_lines,
+    },
+    Transform {
+        id: "format-table",
+        title: "Format Markdown Table",
+        description: "Align the columns of Markdown tables",
+        apply: format_tables,
+    },
+    Transform {
+        id: "format-json",
+        title: "Pretty-print JSON",
+        description: "Indent JSON, keeping the key order",
+        apply: format_json,
+    },
+];
+
+pub fn find(id: &str) -> Option<&'static Transform> {
+    TRANSFORMS.iter().find(|transform| transform.id == id)
+}
+
+const SMALL_WORDS: &[&str] = &[
+    "a", "an", "and", "as", "at", "but", "by", "for", "in", "nor", "of", "on", "or", "per", "the", "to", "via", "vs",
+];
+
+fn title_case(text: &str) -> Result<String, String> {
+    let lines: Vec<String> = text
+        .split('\n')
+        .map(|line| {
+            // An all-caps line is treated as lowercase so it doesn't read as a run of acronyms
+            let line = if line.chars().any(char::is_lowercase) { line.to_string() } else { line.to_lowercase() };
+            let words: Vec<&str> = line.split(' ').collect();
+            let first = words.iter().position(|word| !word.is_empty());
+            let last = words.iter().rposition(|word| !word.is_empty());
+            words
+                .iter()
+                .enumerate()
+                .map(|(index, word)| {
+                    let lower = word.to_lowercase();
+                    if Some(index) != first && Some(index) != last && SMALL_WORDS.contains(&lower.as_str()) {
+                        lower
+                    } else {
+                        capitalize(word)
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect();
+    Ok(lines.join("\n"))
+}
+
+// Uppercase the first letter and lowercase the rest, unless the word is an acronym
+fn capitalize(word: &str) -> String {
+    let acronym = word.chars().filter(|c| c.is_alphabetic()).count() > 1 && !word.chars().any(char::is_lowercase);
+    let mut seen_letter = false;
+    word.chars()
+        .flat_map(|c| {
+            if !c.is_alphabetic() {
+                return vec![c];
+            }
+            let first = !seen_letter;
+            seen_letter = true;
+            if first {
+                c.to_uppercase().collect()
+            } else if acronym {
+                vec![c]
+            } else {
+                c.to_lowercase().collect()
+            }
+        })
+        .collect()
+}
+
+fn sentence_case(text: &str) -> Result<String, String> {
+    let lower = text.to_lowercase();
+    let mut result = String::with_capacity(lower.len());
+    let mut sentence_start = true;
+    let mut after_terminator = false;
+    let mut previous_newline = false;
+    for c in lower.chars() {
+        if c.is_alphabetic() {
+            if sentence_start {
+                result.extend(c.to_uppercase());
+            } else {
+                result.push(c);
+            }
+            sentence_start = false;
+            after_terminator = false;
+        } else {
+            if matches!(c, '.' | '!' | '?') {
+                after_terminator = true;
+            } else if c.is_whitespace() && after_terminator {
+                sentence_start = true;
+            } else if !c.is_whitespace() && !matches!(c, '"' | '\'' | ')' | ']') {
+                after_terminator = false;
+            }
+            // A blank line always starts a new sentence
+            if c == '\n' && previous_newline {
+                sentence_start = true;
+            }
+            result.push(c);
+        }
+        if c != '\r' {
+            previous_newline = c == '\n';
+        }
+    }
+    // The pronoun "I" stays uppercase
+    let words: Vec<&str> = result.split(' ').collect();
+    Ok(words
+        .iter()
+        .map(|word| {
+            let bare = word.trim_matches(|c: char| !c.is_alphanumeric() && c != '\'');
+            if bare == "i" || bare.starts_with("i'") {
+                word.replacen('i', "I", 1)
+            } else {
+                word.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" "))
+}
+
+fn snake_case(text: &str) -> Result<String, String> {
+    Ok(map_lines(text, |line| split_identifier_words(line).join("_")))
+}
+
+fn kebab_case(text: &str) -> Result<String, String> {
+    Ok(map_lines(text, |line| split_identifier_words(line).join("-")))
+}
+
+// Lowercase words of a phrase or identifier, splitting on punctuation and camelCase boundaries
+fn split_identifier_words(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    for part in line.split(|c: char| !c.is_alphanumeric()).filter(|part| !part.is_empty()) {
+        let chars: Vec<char> = part.chars().collect();
+        let mut word = String::new();
+        for (index, &c) in chars.iter().enumerate() {
+            let boundary = index > 0
+                && c.is_uppercase()
+                && (chars[index - 1].is_lowercase()
+                    || chars[index - 1].is_numeric()
+                    // The last capital of an acronym starts the next word, as in "HTTPServer"
+                    || chars.get(index + 1).is_some_and(|next| next.is_lowercase()) && chars[index - 1].is_uppercase());
+            if boundary && !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            word.extend(c.to_lowercase());
+        }
+        words.push(word);
+    }
+    words
+}
+
+fn sort_lines(text: &str) -> Result<String, String> {
+    let (body, trailing) = split_trailing_newline(text);
+    let mut lines: Vec<&str> = body.lines().collect();
+    lines.sort_by(|a, b| a.to_lowercase().cmp(&b.to_lowercase()).then_with(|| a.cmp(b)));
+    Ok(lines.join("\n") + trailing)
+}
+
+fn dedupe_lines(text: &str) -> Result<String, String> {
+    let (body, trailing) = split_trailing_newline(text);
+    let mut seen = std::collections::HashSet::new();
+    let lines: Vec<&str> = body.lines().filter(|line| seen.insert(*line)).collect();
+    Ok(lines.join("\n") + trailing)
+}
+
+fn trim_whitespace(text: &str) -> Result<String, String> {
+    let mut lines: Vec<&str> = Vec::new();
+    for line in text.lines().map(str::trim_end) {
+        if line.is_empty() && lines.last().is_some_and(|previous| previous.is_empty()) {
+            continue;
+        }
+        lines.push(line);
+    }
+    Ok(lines.join("\n").trim_matches('\n').to_string())
+}
+
+fn normalize_quotes(text: &str) -> Result<String, String> {
+    Ok(text
+        .chars()
+        .map(|c| match c {
+            '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}' | '\u{2032}' => '\'',
+            '\u{201C}' | '\u{201D}' | '\u{201E}' | '\u{201F}' | '\u{2033}' => '"',
+            c => c,
+        })
+        .collect())
+}
+
+// Re-wrap each paragraph, keeping the indentation of its first line. List items and quoted lines
+// start paragraphs of their own, so a list isn't merged into a single line. Runs of blank
+// lines separate blocks, as in chunking, and are kept as they were.
+fn wrap_lines(text: &str) -> Result<String, String> {
+    let mut wrapped = String::new();
+    let mut block = Vec::new();
+    for line in text.split_inclusive('\n') {
+        if line.trim().is_empty() {
+            wrapped.push_str(&wrap_block(&block));
+            block.clear();
+            wrapped.push_str(line);
+        } else {
+            block.push(line);
+        }
+    }
+    wrapped.push_str(&wrap_block(&block));
+    Ok(wrapped)
+}
+
+// The wrapped lines use the block's line ending, \n or \r\n, and the block ends as it did
+fn wrap_block(lines: &[&str]) -> String {
+    let Some(last) = lines.last() else {
+        return String::new();
+    };
+    let newline = if lines[0].ends_with("\r\n") { "\r\n" } else { "\n" };
+    let ending = &last[last.trim_end_matches(['\r', '\n']).len()..];
+    let mut paragraphs: Vec<Vec<&str>> = Vec::new();
+    for line in lines.iter().map(|line| line.trim_end_matches(['\r', '\n'])) {
+        match paragraphs.last_mut() {
+            Some(paragraph) if line_marker(line).is_none() => paragraph.push(line),
+            _ => paragraphs.push(vec![line]),
+        }
+    }
+    let mut block = paragraphs.iter().flat_map(|lines| wrap_paragraph(lines)).collect::<Vec<_>>().join(newline);
+    block.push_str(ending);
+    block
+}
+
+// Lines wrapped from a list item are indented under its text, those of a quote stay quoted
+fn wrap_paragraph(lines: &[&str]) -> Vec<String> {
+    let first = lines[0];
+    let indent: String = first.chars().take_while(|c| *c == ' ' || *c == '\t').collect();
+    let (marker, continuation) = match line_marker(first) {
+        Some((marker, true)) => (marker, format!("{}{}", indent, marker)),
+        Some((marker, false)) => (marker, format!("{}{}", indent, " ".repeat(marker.chars().count()))),
+        None => ("", indent.clone()),
+    };
+    let words = first[indent.len() + marker.len()..]
+        .split_whitespace()
+        .chain(lines[1..].iter().flat_map(|line| line.split_whitespace()));
+
+    let mut wrapped = Vec::new();
+    let mut line = format!("{}{}", indent, marker);
+    let mut empty = true;
+    for word in words {
+        if !empty && line.chars().count() + 1 + word.chars().count() > WRAP_WIDTH {
+            wrapped.push(std::mem::replace(&mut line, continuation.clone()));
+            empty = true;
+        }
+        if !empty {
+            line.push(' ');
+        }
+        line.push_str(word);
+        empty = false;
+    }
+    wrapped.push(line);
+    wrapped
+}
+
+// The list or quote marker a line starts with after its indentation, including the spaces
+// after it, and whether it is a quote
+fn line_marker(line: &str) -> Option<(&str, bool)> {
+    let rest = line.trim_start_matches([' ', '\t']);
+    if rest.starts_with('>') {
+        let length = rest.len() - rest.trim_start_matches(['>', ' ']).len();
+        return Some((&rest[..length], true));
+    }
+    let bullet = if rest.starts_with(['-', '*', '+']) {
+        1
+    } else {
+        let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        if digits == 0 || !rest[digits..].starts_with(['.', ')']) {
+            return None;
+        }
+        digits + 1
+    };
+    let spaces = rest[bullet..].len() - rest[bullet..].trim_start_matches(' ').len();
+    (spaces > 0).then(|| (&rest[..bullet + spaces], false))
+}
+
+// Align every block of consecutive lines that forms a Markdown table, leaving other lines as they are
+fn format_tables(text: &str) -> Result<String, String> {
+    let (body, trailing) = split_trailing_newline(text);
+    let lines: Vec<&str> = body.lines().collect();
+    let mut output = Vec::with_capacity(lines.len());
+    let mut index = 0;
+    while index < lines.len() {
+        let end = index + lines[index..].iter().take_while(|line| line.trim_start().starts_with('|')).count();
+        let is_table = end >= index + 2 && split_row(lines[index + 1]).iter().all(|cell| is_separator_cell(cell));
+        if is_table {
+            output.extend(format_table(&lines[index..end]));
+            index = end;
+        } else {
+            output.push(lines[index].to_string());
+            index += 1;
+        }
+    }
+    Ok(output.join("\n") + trailing)
+}
+
+#[derive(Clone, Copy)]
+enum Alignment {
+    None,
+    Left,
+    Center,
+    Right,
+}
+
+fn format_table(lines: &[&str]) -> Vec<String> {
+    let rows: Vec<Vec<String>> = lines.iter().map(|line| split_row(line)).collect();
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let alignments: Vec<Alignment> = (0..columns)
+        .map(|column| {
+            let cell = rows[1].get(column).map(String::as_str).unwrap_or("");
+            match (cell.starts_with(':'), cell.ends_with(':') && cell.len() > 1) {
+                (true, true) => Alignment::Center,
+                (true, false) => Alignment::Left,
+                (false, true) => Alignment::Right,
+                (false, false) => Alignment::None,
+            }
+        })
+        .collect();
+    let widths: Vec<usize> = (0..columns)
+        .map(|column| {
+            rows.iter()
+                .enumerate()
+                .filter(|(index, _)| *index != 1)
+                .filter_map(|(_, row)| row.get(column))
+                .map(|cell| cell.chars().count())
+                .max()
+                .unwrap_or(0)
+                .max(3)
+        })
+        .collect();
+
+    rows.iter()
+        .enumerate()
+        .map(|(index, row)| {
+            let cells: Vec<String> = (0..columns)
+                .map(|column| {
+                    let width = widths[column];
+                    if index == 1 {
+                        return match alignments[column] {
+                            Alignment::None => "-".repeat(width),
+                            Alignment::Left => format!(":{}", "-".repeat(width - 1)),
+                            Alignment::Center => format!(":{}:", "-".repeat(width - 2)),
+                            Alignment::Right => format!("{}:", "-".repeat(width - 1)),
+                        };
+                    }
+                    let cell = row.get(column).map(String::as_str).unwrap_or("");
+                    match alignments[column] {
+                        Alignment::Center => format!("{:^width$}", cell),
+                        Alignment::Right => format!("{:>width$}", cell),
+                        Alignment::None | Alignment::Left => format!("{:<width$}", cell),
+                    }
+                })
+                .collect();
+            format!("| {} |", cells.join(" | "))
+        })
+        .collect()
+}
+
+// Cells of a table row, honoring escaped pipes
+fn split_row(line: &str) -> Vec<String> {
+    let line = line.trim();
+    let line = line.strip_prefix('|').unwrap_or(line);
+    let line = if line.ends_with('|') && !line.ends_with("\\|") { &line[..line.len() - 1] } else { line };
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut escaped = false;
+    for c in line.chars() {
+        if c == '|' && !escaped {
+            cells.push(cell.trim().to_string());
+            cell.clear();
+        } else {
+            cell.push(c);
+        }
+        escaped = c == '\\' && !escaped;
+    }
+    cells.push(cell.trim().to_string());
+    cells
+}
+
+fn is_separator_cell(cell: &str) -> bool {
+    let dashes = cell.trim_start_matches(':').trim_end_matches(':');
+    !dashes.is_empty() && dashes.chars().all(|c| c == '-')
+}
+
+// Re-indent without parsing into a map, which would sort the keys
+fn format_json(text: &str) -> Result<String, String> {
+    serde_json::from_str::<serde_json::Value>(text).map_err(|e| format!("Invalid JSON: {}", e))?;
+
+    let mut output = String::with_capacity(text.len() * 2);
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut chars = text.trim().chars().peekable();
+    while let Some(c) = chars.next() {
+        if in_string {
+            output.push(c);
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+        match c {
+            '"' => {
+                in_string = true;
+                output.push(c);
+            }
+            '{' | '[' => {
+                output.push(c);
+                // Keep empty objects and arrays on one line
+                while chars.peek().is_some_and(|next| next.is_whitespace()) {
+                    chars.next();
+                }
+                if chars.peek().is_some_and(|next| *next == '}' || *next == ']') {
+                    output.push(chars.next().unwrap_or_default());
+                } else {
+                    depth += 1;
+                    push_newline(&mut output, depth);
+                }
+            }
+            '}' | ']' => {
+                depth = depth.saturating_sub(1);
+                push_newline(&mut output, depth);
+                output.push(c);
+            }
+            ',' => {
+                output.push(c);
+                push_newline(&mut output, depth);
+            }
+            ':' => output.push_str(": "),
+            c if c.is_whitespace() => {}
+            c => output.push(c),
+        }
+    }
+    Ok(output)
+}
+
+fn push_newline(output: &mut String, depth: usize) {
+    output.push('\n');
+    output.push_str(&"  ".repeat(depth));
+}
+
+fn map_lines(text: &str, transform: impl Fn(&str) -> String) -> String {
+    text.split('\n').map(|line| transform(line.trim_end_matches('\r'))).collect::<Vec<_>>().join("\n")
+}
+
+// Line-based transforms keep a final newline if the input had one
+fn split_trailing_newline(text: &str) -> (&str, &'static str) {
+    match text.strip_suffix('\n') {
+        Some(body) => (body.strip_suffix('\r').unwrap_or(body), "\n"),
+        None => (text, ""),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_transforms_by_id() {
+        assert!(find("wrap-lines").is_some());
+        assert!(find("proofread").is_none());
+    }
+
+    #[test]
+    fn title_case_keeps_small_words_and_acronyms() {
+        assert_eq!(title_case("a guide to NASA and the ESA").unwrap(), "A Guide to NASA and the ESA");
+        assert_eq!(
+            title_case("the lord of the rings\nWAR AND PEACE").unwrap(),
+            "The Lord of the Rings\nWar and Peace"
+        );
+    }
+
+    #[test]
+    fn sentence_case_capitalizes_sentences_and_i() {
+        assert_eq!(
+            sentence_case("HELLO THERE. how are you? i'm fine, i think").unwrap(),
+            "Hello there. How are you? I'm fine, I think"
+        );
+        assert_eq!(
+            sentence_case("first line\n\nsecond \"quote.\" next").unwrap(),
+            "First line\n\nSecond \"quote.\" Next"
+        );
+    }
+
+    #[test]
+    fn snake_case_splits_camel_case_and_acronyms() {
+        assert_eq!(snake_case("HTTPServer errorCode\nSome Title!").unwrap(), "http_server_error_code\nsome_title");
+    }
+
+    #[test]
+    fn kebab_case_converts_each_line() {
+        assert_eq!(kebab_case("Hello World\r\nfooBar2Baz").unwrap(), "hello-world\nfoo-bar2-baz");
+    }
+
+    #[test]
+    fn sort_lines_ignores_case_and_keeps_final_newline() {
+        assert_eq!(sort_lines("banana\nApple\ncherry\napple\n").unwrap(), "Apple\napple\nbanana\ncherry\n");
+    }
+
+    #[test]
+    fn dedupe_lines_keeps_first_occurrence() {
+        assert_eq!(dedupe_lines("a\nb\na\nc\nb").unwrap(), "a\nb\nc");
+    }
+
+    #[test]
+    fn trim_whitespace_collapses_blank_lines() {
+        assert_eq!(trim_whitespace("\n\n  a  \n\n\n\nb\t\n\n").unwrap(), "  a\n\nb");
+    }
+
+    #[test]
+    fn normalize_quotes_straightens_quotes() {
+        assert_eq!(
+            normalize_quotes("\u{201C}Hi,\u{201D} she said, \u{2018}it\u{2019}s fine\u{2019}").unwrap(),
+            "\"Hi,\" she said, 'it's fine'"
+        );
+    }
+
+    #[test]
+    fn wrap_lines_reflows_paragraphs() {
+        let words = "word ".repeat(30);
+        let wrapped = wrap_lines(&format!("  {}\n\nshort\nlines", words.trim())).unwrap();
+        let (paragraph, rest) = wrapped.split_once("\n\n").unwrap();
+        assert!(paragraph.lines().all(|line| line.len() <= WRAP_WIDTH && line.starts_with("  word")));
+        assert_eq!(paragraph.split_whitespace().count(), 30);
+        assert_eq!(rest, "short lines");
+    }
+
+    #[test]
+    fn wrap_lines_splits_on_runs_of_blank_lines() {
+        assert_eq!(wrap_lines("a\nb\n\n\nc\nd").unwrap(), "a b\n\n\nc d");
+        assert_eq!(wrap_lines("a\nb\n  \n\nc\n").unwrap(), "a b\n  \n\nc\n");
+        assert_eq!(wrap_lines("\n\na\nb").unwrap(), "\n\na b");
+    }
+
+    #[test]
+    fn wrap_lines_keeps_crlf_line_endings() {
+        assert_eq!(wrap_lines("one\r\ntwo\r\n\r\nthree\r\n").unwrap(), "one two\r\n\r\nthree\r\n");
+        let words = "word ".repeat(30);
+        let wrapped = wrap_lines(&format!("{}\r\n\r\n\r\nend", words.trim())).unwrap();
+        let (paragraph, rest) = wrapped.split_once("\r\n\r\n\r\n").unwrap();
+        assert_eq!(paragraph.lines().count(), 2);
+        assert!(paragraph.contains("word\r\nword") && !paragraph.replace("\r\n", "").contains('\n'));
+        assert_eq!(rest, "end");
+    }
+
+    #[test]
+    fn wrap_lines_keeps_list_items_and_quotes_apart() {
+        assert_eq!(wrap_lines("- a\n- b").unwrap(), "- a\n- b");
+        assert_eq!(
+            wrap_lines("Intro\n* one\n  more\n+ two\n1. three\n2) four").unwrap(),
+            "Intro\n* one more\n+ two\n1. three\n2) four"
+        );
+        assert_eq!(wrap_lines("> quoted\n> lines").unwrap(), "> quoted\n> lines");
+        assert_eq!(wrap_lines("-5 degrees\nand falling").unwrap(), "-5 degrees and falling");
+    }
+
+    #[test]
+    fn wrap_lines_indents_wrapped_list_items_and_quotes() {
+        let words = "word ".repeat(20);
+        let wrapped = wrap_lines(&format!("- {}", words.trim())).unwrap();
+        let lines: Vec<&str> = wrapped.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("- word"));
+        assert!(lines[1].starts_with("  word"));
+
+        let wrapped = wrap_lines(&format!("> {}", words.trim())).unwrap();
+        assert!(wrapped.lines().count() == 2 && wrapped.lines().all(|line| line.starts_with("> word")));
+    }
+
+    #[test]
+    fn format_tables_aligns_columns() {
+        let table = "Before\n|a|b|\n|-|:-:|\n|long cell|x|\n";
+        assert_eq!(
+            format_tables(table).unwrap(),
+            "Before\n| a         |  b  |\n| --------- | :-: |\n| long cell |  x  |\n"
+        );
+        // Without a separator row it isn't a table
+        assert_eq!(format_tables("|a|b|\n|c|d|").unwrap(), "|a|b|\n|c|d|");
+    }
+
+    #[test]
+    fn format_json_keeps_key_order() {
+        assert_eq!(
+            format_json(r#"{"b":1,"a":[1,"x:,"],"c":{}}"#).unwrap(),
+            "{\n  \"b\": 1,\n  \"a\": [\n    1,\n    \"x:,\"\n  ],\n  \"c\": {}\n}"
+        );
+        assert!(format_json("{oops").is_err());
+    }
+}
@@ -0,0 +1,327 @@
+// Token usage and cost of model calls, appended to a local ledger for spend summaries
+use crate::cli;
+use crate::models;
+use chrono::{DateTime, Datelike, Days, Local, NaiveTime, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::sync::{Mutex, RwLock};
+
+const LEDGER_FILE: &str = "usage.jsonl";
+
+// Profile of the running app, recorded with each entry
+static PROFILE: RwLock<Option<String>> = RwLock::new(None);
+// Serializes appends from concurrent requests within this process
+static LEDGER_LOCK: Mutex<()> = Mutex::new(());
+// Records read so far, so budget checks before every call only parse what was appended since
+static LEDGER_CACHE: Mutex<Option<LedgerCache>> = Mutex::new(None);
+
+struct LedgerCache {
+    path: PathBuf,
+    // Bytes of the ledger read into `records`, always ending after a newline
+    offset: u64,
+    records: Vec<UsageRecord>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Usage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    // USD, None when the model's pricing is unknown
+    #[serde(default)]
+    pub cost: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageRecord {
+    // Unix timestamp in seconds
+    pub timestamp: i64,
+    #[serde(default)]
+    pub profile: Option<String>,
+    pub model: String,
+    pub action: String,
+    #[serde(flatten)]
+    pub usage: Usage,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum Period {
+    Day,
+    Week,
+    Month,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SpendTotals {
+    pub requests: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub cost: f64,
+    // Requests whose cost couldn't be computed and isn't included in `cost`
+    pub unpriced_requests: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UsageSummary {
+    pub period: Period,
+    // Start of the period in local time, as a Unix timestamp
+    pub since: i64,
+    #[serde(flatten)]
+    pub totals: SpendTotals,
+    pub by_model: BTreeMap<String, SpendTotals>,
+    pub by_action: BTreeMap<String, SpendTotals>,
+}
+
+impl SpendTotals {
+    fn add(&mut self, usage: &Usage) {
+        self.requests += 1;
+        self.prompt_tokens += usage.prompt_tokens;
+        self.completion_tokens += usage.completion_tokens;
+        match usage.cost {
+            Some(cost) => self.cost += cost,
+            None => self.unpriced_requests += 1,
+        }
+    }
+}
+
+pub fn set_profile(profile: Option<String>) {
+    if let Ok(mut current) = PROFILE.write() {
+        *current = profile;
+    }
+}
+
+pub fn current_profile() -> Option<String> {
+    PROFILE.read().ok().and_then(|profile| profile.clone())
+}
+
+// Ask OpenRouter to report the cost of a call in its `usage` block, which also covers models
+// the model list has no price for
+pub fn request_cost(body: &mut serde_json::Value) {
+    if let Some(body) = body.as_object_mut() {
+        let usage = body
+            .entry("usage")
+            .or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()));
+        match usage.as_object_mut() {
+            Some(usage) => {
+                usage.insert("include".to_string(), serde_json::Value::Bool(true));
+            }
+            None => *usage = serde_json::json!({ "include": true }),
+        }
+    }
+}
+
+// Read the token counts from a completion's `usage` block. The cost is the one OpenRouter
+// reported, or else computed from the model list.
+pub async fn from_completion(api_key: &str, model: &str, completion: &serde_json::Value) -> Option<Usage> {
+    let usage = &completion["usage"];
+    let prompt_tokens = usage["prompt_tokens"].as_u64()?;
+    let completion_tokens = usage["completion_tokens"].as_u64().unwrap_or(0);
+    let cost = match usage["cost"].as_f64() {
+        Some(cost) => Some(cost),
+        None => models::find(api_key, model)
+            .await
+            .and_then(|model| model.pricing)
+            .map(|pricing| pricing.cost(prompt_tokens, completion_tokens)),
+    };
+    Some(Usage {
+        prompt_tokens,
+        completion_tokens,
+        cost,
+    })
+}
+
+// Append a call to the ledger. Failures are logged rather than failing the request.
+pub fn record(model: &str, action: &str, usage: &Usage) {
+    let record = UsageRecord {
+        timestamp: Local::now().timestamp(),
+        profile: current_profile(),
+        model: model.to_string(),
+        action: action.to_string(),
+        usage: usage.clone(),
+    };
+    if let Err(e) = append(&record) {
+        eprintln!("Failed to record usage: {}", e);
+    }
+}
+
+fn append(record: &UsageRecord) -> Result<(), String> {
+    let path = ledger_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let line = serde_json::to_string(record).map_err(|e| e.to_string())?;
+    let _guard = LEDGER_LOCK.lock().map_err(|e| e.to_string())?;
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    writeln!(file, "{}", line).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+// Spend in the current day, week (from Monday) or month, in local time
+pub fn summary(period: Period) -> Result<UsageSummary, String> {
+    let since = period_start(period, Local::now());
+    Ok(summarize(period, since, records_since(since)?))
+}
+
+fn summarize(period: Period, since: i64, records: Vec<UsageRecord>) -> UsageSummary {
+    let mut summary = UsageSummary {
+        period,
+        since,
+        totals: SpendTotals::default(),
+        by_model: BTreeMap::new(),
+        by_action: BTreeMap::new(),
+    };
+    for record in records {
+        summary.totals.add(&record.usage);
+        summary.by_model.entry(record.model).or_default().add(&record.usage);
+        summary.by_action.entry(record.action).or_default().add(&record.usage);
+    }
+    summary
+}
+
+pub fn period_start(period: Period, now: DateTime<Local>) -> i64 {
+    let today = now.date_naive();
+    let start = match period {
+        Period::Day => today,
+        Period::Week => today - Days::new(u64::from(today.weekday().num_days_from_monday())),
+        Period::Month => today.with_day(1).unwrap_or(today),
+    };
+    // A DST change at midnight can make local midnight ambiguous or skip it
+    Local
+        .from_local_datetime(&start.and_time(NaiveTime::MIN))
+        .earliest()
+        .map(|start| start.timestamp())
+        .unwrap_or_else(|| now.timestamp() - 86_400)
+}
+
+pub fn records_since(since: i64) -> Result<Vec<UsageRecord>, String> {
+    let path = ledger_path()?;
+    let mut cache = LEDGER_CACHE.lock().map_err(|e| e.to_string())?;
+    let length = match std::fs::metadata(&path) {
+        Ok(metadata) => metadata.len(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            *cache = None;
+            return Ok(Vec::new());
+        }
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+    // Start over if the ledger was replaced by a shorter one
+    let cache = match cache.take().filter(|cache| cache.path == path && cache.offset <= length) {
+        Some(current) => cache.insert(current),
+        None => cache.insert(LedgerCache {
+            path,
+            offset: 0,
+            records: Vec::new(),
+        }),
+    };
+
+    if cache.offset < length {
+        let mut file = std::fs::File::open(&cache.path)
+            .map_err(|e| format!("Failed to open {}: {}", cache.path.display(), e))?;
+        let mut appended = Vec::new();
+        file.seek(SeekFrom::Start(cache.offset))
+            .and_then(|_| file.read_to_end(&mut appended))
+            .map_err(|e| format!("Failed to read {}: {}", cache.path.display(), e))?;
+        // A last line without a newline may still be being written, so it is read next time
+        let complete = appended.iter().rposition(|b| *b == b'\n').map_or(0, |end| end + 1);
+        cache.records.extend(parse_records(&appended[..complete]));
+        cache.offset += complete as u64;
+    }
+    Ok(cache.records.iter().filter(|record| record.timestamp >= since).cloned().collect())
+}
+
+// Skip lines left incomplete by a crash instead of failing the whole summary
+fn parse_records(lines: &[u8]) -> impl Iterator<Item = UsageRecord> + '_ {
+    lines
+        .split(|b| *b == b'\n')
+        .filter_map(|line| serde_json::from_slice::<UsageRecord>(line).ok())
+}
+
+// Next to the settings store so the app and the CLI share one ledger
+fn ledger_path() -> Result<PathBuf, String> {
+    cli::app_data_path(LEDGER_FILE).ok_or_else(|| "Failed to locate the app data directory".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Timelike;
+
+    fn local(year: i32, month: u32, day: u32, hour: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(year, month, day, hour, 30, 0).earliest().unwrap()
+    }
+
+    fn usage(cost: Option<f64>) -> Usage {
+        Usage {
+            prompt_tokens: 100,
+            completion_tokens: 50,
+            cost,
+        }
+    }
+
+    #[test]
+    fn period_start_is_local_midnight() {
+        // Thursday 2025-06-12
+        let now = local(2025, 6, 12, 15);
+        let start = |period| Local.timestamp_opt(period_start(period, now), 0).unwrap();
+
+        let day = start(Period::Day);
+        assert_eq!((day.day(), day.hour(), day.minute()), (12, 0, 0));
+        let week = start(Period::Week);
+        assert_eq!((week.day(), week.weekday(), week.hour()), (9, chrono::Weekday::Mon, 0));
+        let month = start(Period::Month);
+        assert_eq!((month.month(), month.day(), month.hour()), (6, 1, 0));
+
+        // A week can start in the previous month
+        let week = Local.timestamp_opt(period_start(Period::Week, local(2025, 6, 1, 9)), 0).unwrap();
+        assert_eq!((week.month(), week.day()), (5, 26));
+    }
+
+    #[test]
+    fn parse_records_skips_broken_lines() {
+        let lines = concat!(
+            r#"{"timestamp":1,"model":"a/x","action":"proofread","prompt_tokens":3,"completion_tokens":4,"cost":0.5}"#,
+            "\n{\"timestamp\":2,\"model\n",
+            r#"{"timestamp":3,"profile":"work","model":"b/y","action":"draft","prompt_tokens":1,"completion_tokens":2}"#,
+            "\n"
+        );
+        let records: Vec<UsageRecord> = parse_records(lines.as_bytes()).collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].usage.cost, Some(0.5));
+        assert_eq!(records[1].profile.as_deref(), Some("work"));
+        assert_eq!(records[1].usage.cost, None);
+    }
+
+    #[test]
+    fn summary_groups_by_model_and_action() {
+        let record = |model: &str, action: &str, cost| UsageRecord {
+            timestamp: 10,
+            profile: None,
+            model: model.to_string(),
+            action: action.to_string(),
+            usage: usage(cost),
+        };
+        let summary = summarize(
+            Period::Day,
+            0,
+            vec![
+                record("a/x", "proofread", Some(0.25)),
+                record("a/x", "draft", Some(0.5)),
+                record("b/y", "proofread", None),
+            ],
+        );
+        assert_eq!(summary.totals.requests, 3);
+        assert_eq!(summary.totals.prompt_tokens, 300);
+        assert_eq!(summary.totals.completion_tokens, 150);
+        // The unpriced call is counted apart rather than as free
+        assert_eq!(summary.totals.cost, 0.75);
+        assert_eq!(summary.totals.unpriced_requests, 1);
+        assert_eq!(summary.by_model["a/x"].requests, 2);
+        assert_eq!(summary.by_model["b/y"].unpriced_requests, 1);
+        assert_eq!(summary.by_action["proofread"].cost, 0.25);
+    }
+}
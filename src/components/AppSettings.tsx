@@ -2,11 +2,42 @@ import React, { useState, useEffect } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import { useTheme } from '../contexts/ThemeContext';
 
+interface HttpApiSettings {
+  enabled: boolean;
+  port: number;
+  token: string;
+}
+
+interface WatcherRule {
+  name: string;
+  pattern: string | null;
+  min_length: number | null;
+  source_pattern: string | null;
+  action: string;
+  tone: string | null;
+  model: string;
+  mode: 'offer' | 'auto';
+}
+
+interface WatcherSettings {
+  enabled: boolean;
+  poll_interval_ms: number;
+  rules: WatcherRule[];
+}
+
 const AppSettings: React.FC = () => {
   const { theme, setTheme } = useTheme();
   const [shortcut, setShortcut] = useState('');
   const [autostart, setAutostart] = useState(false);
   const [autoClose, setAutoClose] = useState(true);
+  const [httpApi, setHttpApi] = useState<HttpApiSettings>({ enabled: false, port: 4317, token: '' });
+  const [httpApiPort, setHttpApiPort] = useState('4317');
+  const [showToken, setShowToken] = useState(false);
+  const [httpApiError, setHttpApiError] = useState<string | null>(null);
+  const [watcherRules, setWatcherRules] = useState<WatcherRule[]>([]);
+  const [pollInterval, setPollInterval] = useState('1000');
+  const [watcherMessage, setWatcherMessage] = useState<string | null>(null);
+  const [actions, setActions] = useState<{ id: string; title: string }[]>([]);
 
   // Load settings from backend when component mounts
   useEffect(() => {
@@ -28,6 +59,84 @@ const AppSettings: React.FC = () => {
     } catch (error) {
       console.error('Failed to load app settings:', error);
     }
+    loadHttpApi();
+    invoke<WatcherSettings>('get_clipboard_watcher')
+      .then((settings) => {
+        setWatcherRules(settings.rules);
+        setPollInterval(String(settings.poll_interval_ms));
+      })
+      .catch((error) => console.error('Failed to load clipboard watcher settings:', error));
+    invoke<{ id: string; title: string }[]>('list_actions')
+      .then(setActions)
+      .catch(console.error);
+  };
+
+  const updateRule = (index: number, changes: Partial<WatcherRule>) => {
+    setWatcherRules(watcherRules.map((rule, i) => (i === index ? { ...rule, ...changes } : rule)));
+  };
+
+  const addRule = () => {
+    setWatcherRules([...watcherRules, {
+      name: `Rule ${watcherRules.length + 1}`,
+      pattern: null,
+      min_length: null,
+      source_pattern: null,
+      action: 'proofread',
+      tone: null,
+      model: localStorage.getItem('selected_model') || '',
+      mode: 'offer',
+    }]);
+  };
+
+  // The watcher is turned on and off from the tray, so the current state is kept when saving rules
+  const saveWatcher = async () => {
+    try {
+      const current = await invoke<WatcherSettings>('get_clipboard_watcher');
+      const rules = watcherRules.map((rule) => ({
+        ...rule,
+        pattern: rule.pattern || null,
+        source_pattern: rule.source_pattern || null,
+        tone: rule.action === 'tone' ? rule.tone || 'professional' : null,
+      }));
+      await invoke('set_clipboard_watcher', {
+        settings: { ...current, poll_interval_ms: Number(pollInterval) || 1000, rules },
+      });
+      setWatcherMessage('Clipboard watcher rules saved');
+    } catch (error) {
+      setWatcherMessage(`Failed to save clipboard watcher rules: ${error}`);
+    }
+  };
+
+  const loadHttpApi = () => {
+    invoke<HttpApiSettings>('get_http_api')
+      .then((settings) => {
+        setHttpApi(settings);
+        setHttpApiPort(String(settings.port));
+      })
+      .catch((error) => console.error('Failed to load HTTP API settings:', error));
+  };
+
+  // Saving restarts the server, and fails if the port can't be bound
+  const saveHttpApi = async (settings: HttpApiSettings) => {
+    try {
+      const saved = await invoke<HttpApiSettings>('set_http_api', { settings });
+      setHttpApi(saved);
+      setHttpApiPort(String(saved.port));
+      setHttpApiError(null);
+    } catch (error) {
+      setHttpApiError(String(error));
+      loadHttpApi();
+    }
+  };
+
+  const regenerateToken = async () => {
+    try {
+      setHttpApi(await invoke<HttpApiSettings>('regenerate_http_api_token'));
+      setShowToken(true);
+      setHttpApiError(null);
+    } catch (error) {
+      setHttpApiError(String(error));
+    }
   };
 
   const handleShortcutChange = (e: React.KeyboardEvent<HTMLInputElement>) => {
@@ -161,6 +270,195 @@ const AppSettings: React.FC = () => {
           </div>
         </section>
 
+        {/* HTTP API Section */}
+        <section className="app-settings__section">
+          <h3 className="app-settings__section-title">HTTP API</h3>
+          <div className="app-settings__setting-row">
+            <div className="app-settings__checkbox-container">
+              <label className="app-settings__checkbox-label">
+                <input
+                  type="checkbox"
+                  checked={httpApi.enabled}
+                  onChange={(e) => saveHttpApi({ ...httpApi, enabled: e.target.checked, port: Number(httpApiPort) })}
+                  className="app-settings__checkbox"
+                />
+                <span>Enable</span>
+              </label>
+            </div>
+            <div className="app-settings__input-row">
+              <label className="app-settings__setting-label">
+                Port:
+              </label>
+              <div className="app-settings__input-container">
+                <input
+                  type="number"
+                  min="1"
+                  max="65535"
+                  value={httpApiPort}
+                  onChange={(e) => setHttpApiPort(e.target.value)}
+                  className="app-settings__shortcut-input"
+                />
+              </div>
+              <button
+                onClick={() => saveHttpApi({ ...httpApi, port: Number(httpApiPort) })}
+                className="app-settings__action-button"
+              >
+                Save
+              </button>
+            </div>
+            <div className="app-settings__input-row">
+              <label className="app-settings__setting-label">
+                Token:
+              </label>
+              <div className="app-settings__input-container">
+                <input
+                  type={showToken ? 'text' : 'password'}
+                  value={httpApi.token}
+                  placeholder="Generated when the API is enabled"
+                  className="app-settings__shortcut-input"
+                  readOnly
+                />
+              </div>
+              <button onClick={() => setShowToken(!showToken)} className="app-settings__action-button">
+                {showToken ? 'Hide' : 'Show'}
+              </button>
+              <button onClick={regenerateToken} className="app-settings__action-button">
+                Regenerate
+              </button>
+            </div>
+            {httpApiError && (
+              <div className="app-settings__setting-description">
+                {httpApiError}
+              </div>
+            )}
+            <div className="app-settings__setting-description">
+              Serves the actions and an OpenAI-compatible proxy on http://127.0.0.1:{httpApi.port} for editors and scripts. Requests must send the token as {'"Authorization: Bearer <token>"'}.
+            </div>
+          </div>
+        </section>
+
+        {/* Clipboard Watcher Section */}
+        <section className="app-settings__section">
+          <h3 className="app-settings__section-title">Clipboard Watcher</h3>
+          {watcherRules.map((rule, index) => (
+            <div key={index} className="app-settings__setting-row">
+              <div className="app-settings__input-row">
+                <div className="app-settings__input-container">
+                  <input
+                    type="text"
+                    value={rule.name}
+                    onChange={(e) => updateRule(index, { name: e.target.value })}
+                    placeholder="Rule name"
+                    className="app-settings__shortcut-input"
+                  />
+                </div>
+                <button
+                  onClick={() => setWatcherRules(watcherRules.filter((_, i) => i !== index))}
+                  className="app-settings__action-button"
+                >
+                  Remove
+                </button>
+              </div>
+              <div className="app-settings__input-container">
+                <input
+                  type="text"
+                  value={rule.pattern ?? ''}
+                  onChange={(e) => updateRule(index, { pattern: e.target.value })}
+                  placeholder="Copied text matches regex (optional)"
+                  className="app-settings__shortcut-input"
+                />
+              </div>
+              <div className="app-settings__input-container">
+                <input
+                  type="number"
+                  min="1"
+                  value={rule.min_length ?? ''}
+                  onChange={(e) => updateRule(index, { min_length: e.target.value === '' ? null : Number(e.target.value) })}
+                  placeholder="Minimum length in characters (optional)"
+                  className="app-settings__shortcut-input"
+                />
+              </div>
+              <div className="app-settings__input-container">
+                <input
+                  type="text"
+                  value={rule.source_pattern ?? ''}
+                  onChange={(e) => updateRule(index, { source_pattern: e.target.value })}
+                  placeholder="Source app or window title matches regex (optional)"
+                  className="app-settings__shortcut-input"
+                />
+              </div>
+              <div className="app-settings__select-container">
+                <select
+                  value={rule.action}
+                  onChange={(e) => updateRule(index, { action: e.target.value })}
+                  className="app-settings__theme-select"
+                >
+                  {actions.map((action) => (
+                    <option key={action.id} value={action.id}>
+                      {action.title}
+                    </option>
+                  ))}
+                </select>
+                {rule.action === 'tone' && (
+                  <input
+                    type="text"
+                    value={rule.tone ?? ''}
+                    onChange={(e) => updateRule(index, { tone: e.target.value })}
+                    placeholder="Tone, e.g. professional"
+                    className="app-settings__shortcut-input"
+                  />
+                )}
+                <select
+                  value={rule.mode}
+                  onChange={(e) => updateRule(index, { mode: e.target.value as WatcherRule['mode'] })}
+                  className="app-settings__theme-select"
+                >
+                  <option value="offer">Notify and offer the action</option>
+                  <option value="auto">Run it and copy the result</option>
+                </select>
+              </div>
+              <div className="app-settings__input-container">
+                <input
+                  type="text"
+                  value={rule.model}
+                  onChange={(e) => updateRule(index, { model: e.target.value })}
+                  placeholder="Model id or alias"
+                  className="app-settings__shortcut-input"
+                />
+              </div>
+            </div>
+          ))}
+          <div className="app-settings__input-row">
+            <label className="app-settings__setting-label">
+              Check every (ms):
+            </label>
+            <div className="app-settings__input-container">
+              <input
+                type="number"
+                min="250"
+                step="250"
+                value={pollInterval}
+                onChange={(e) => setPollInterval(e.target.value)}
+                className="app-settings__shortcut-input"
+              />
+            </div>
+            <button onClick={addRule} className="app-settings__action-button">
+              Add Rule
+            </button>
+            <button onClick={saveWatcher} className="app-settings__action-button">
+              Save
+            </button>
+          </div>
+          {watcherMessage && (
+            <div className="app-settings__setting-description">
+              {watcherMessage}
+            </div>
+          )}
+          <div className="app-settings__setting-description">
+            Turn the watcher on from the tray menu. Copied text runs the first rule whose conditions all match; empty conditions always match.
+          </div>
+        </section>
+
         {/* Theme Selection Section */}
         <section className="app-settings__section">
           <h3 className="app-settings__section-title">Theme:</h3>
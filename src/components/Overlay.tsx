@@ -1,15 +1,96 @@
 import React, { useState, useEffect } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import { getCurrentWindow } from '@tauri-apps/api/window';
-import { Edit3, MessageSquare, PenTool, Loader2, Copy, Check } from 'lucide-react';
+import { listen } from '@tauri-apps/api/event';
+import { Edit3, MessageSquare, PenTool, Terminal, Sparkles, Wand2, Layers, Loader2, Copy, Check } from 'lucide-react';
 
 interface OverlayProps {}
 
+interface ActionInfo {
+  id: string;
+  title: string;
+  description: string;
+  kind: 'ai' | 'command' | 'local';
+}
+
+interface Usage {
+  prompt_tokens: number;
+  completion_tokens: number;
+  cost: number | null;
+}
+
+interface ActionOutput {
+  text: string;
+  usage: Usage | null;
+  budget_warning: string | null;
+  reasoning: string | null;
+}
+
+interface Estimate {
+  prompt_tokens: number;
+  expected_completion_tokens: number;
+  expected_cost: number | null;
+  warning: string | null;
+}
+
+const formatTokens = (tokens: number) =>
+  tokens >= 1000 ? `${(tokens / 1000).toFixed(1)}k` : `${tokens}`;
+
+const formatEstimate = (estimate: Estimate) => {
+  const tokens = `~${formatTokens(estimate.prompt_tokens + estimate.expected_completion_tokens)} tokens`;
+  return estimate.expected_cost === null ? tokens : `${tokens}, ~$${estimate.expected_cost.toFixed(4)}`;
+};
+
+const formatUsage = (usage: Usage) => {
+  const tokens = `${usage.prompt_tokens + usage.completion_tokens} tokens`;
+  return usage.cost === null ? tokens : `${tokens} · $${usage.cost.toFixed(4)}`;
+};
+
+const BUILT_IN_ACTIONS = ['proofread', 'tone', 'draft'];
+
+interface Pipeline {
+  id: string;
+  name: string;
+  steps: { action: string }[];
+}
+
+interface PipelineResult {
+  steps: { action: string; model: string; output: string | null; error: string | null; usage: Usage | null }[];
+  output: string | null;
+  error: string | null;
+}
+
+// Pipelines are selected like actions, under a prefix so their ids can't clash with action ids
+const PIPELINE_PREFIX = 'pipeline:';
+
+// Usage of all steps together, without a cost if any step's cost is unknown
+const totalUsage = (usages: Usage[]): Usage | null =>
+  usages.length === 0 ? null : usages.reduce((total, usage) => ({
+    prompt_tokens: total.prompt_tokens + usage.prompt_tokens,
+    completion_tokens: total.completion_tokens + usage.completion_tokens,
+    cost: total.cost === null || usage.cost === null ? null : total.cost + usage.cost,
+  }));
+
+interface ClipboardWatcherMatch {
+  rule: string;
+  action: string;
+  tone: string | null;
+  model: string;
+  text: string;
+}
+
 const Overlay: React.FC<OverlayProps> = () => {
   const [inputText, setInputText] = useState('');
+  const [inputImage, setInputImage] = useState<string | null>(null);
   const [outputText, setOutputText] = useState('');
+  const [outputUsage, setOutputUsage] = useState<Usage | null>(null);
+  const [budgetWarning, setBudgetWarning] = useState<string | null>(null);
+  const [reasoning, setReasoning] = useState<string | null>(null);
+  const [estimate, setEstimate] = useState<Estimate | null>(null);
   const [isLoading, setIsLoading] = useState(false);
-  const [selectedAction, setSelectedAction] = useState<'proofread' | 'tone' | 'draft'>('proofread');
+  const [selectedAction, setSelectedAction] = useState('proofread');
+  const [actions, setActions] = useState<ActionInfo[]>([]);
+  const [pipelines, setPipelines] = useState<Pipeline[]>([]);
   const [selectedTone, setSelectedTone] = useState('professional');
   const [copied, setCopied] = useState(false);
   const [autoCloseEnabled, setAutoCloseEnabled] = useState(false);
@@ -24,6 +105,46 @@ const Overlay: React.FC<OverlayProps> = () => {
 
     // Load auto_close setting
     invoke<boolean>('get_auto_close').then(setAutoCloseEnabled);
+
+    // Load built-in and custom actions
+    invoke<ActionInfo[]>('list_actions').then(setActions).catch(console.error);
+    invoke<Pipeline[]>('get_pipelines').then(setPipelines).catch(console.error);
+
+    // Preselect the action requested with --action at launch
+    invoke<string | null>('take_launch_action').then((action) => {
+      if (action) {
+        setSelectedAction(action);
+      }
+    });
+  }, []);
+
+  // Preselect the action forwarded from a second `wraiter --action <id>` launch
+  useEffect(() => {
+    const unlisten = listen<string>('launch-action', () => {
+      invoke<string | null>('take_launch_action').then((action) => {
+        if (action) {
+          setSelectedAction(action);
+        }
+      });
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  // Preselect the action offered by the clipboard watcher
+  useEffect(() => {
+    const unlisten = listen<ClipboardWatcherMatch>('clipboard-watcher-match', (event) => {
+      const { action, tone, text } = event.payload;
+      setSelectedAction(action);
+      if (tone) {
+        setSelectedTone(tone);
+      }
+      setInputText(text);
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
   }, []);
 
   // Handle window focus to refresh clipboard content (but only when not auto-closing)
@@ -38,22 +159,68 @@ const Overlay: React.FC<OverlayProps> = () => {
     return () => window.removeEventListener('focus', handleFocus);
   }, [selectedAction, autoCloseEnabled]);
 
+  // Estimate tokens and cost of AI actions while the input is edited
+  useEffect(() => {
+    const apiKey = localStorage.getItem('openrouter_api_key') || '';
+    const model = localStorage.getItem('selected_model') || '';
+    const isAiAction = (actions.find(a => a.id === selectedAction)?.kind ?? 'ai') === 'ai';
+    if (!inputText.trim() || !model || !isAiAction || selectedAction.startsWith(PIPELINE_PREFIX)) {
+      setEstimate(null);
+      return;
+    }
+
+    const timeout = setTimeout(() => {
+      invoke<Estimate>('estimate_request', {
+        text: inputText,
+        action: selectedAction,
+        model,
+        apiKey,
+        tone: selectedAction === 'tone' ? selectedTone : undefined,
+        maxTokens: parseInt(localStorage.getItem('max_tokens') || '2000')
+      })
+        .then(setEstimate)
+        .catch(() => setEstimate(null));
+    }, 400);
+    return () => clearTimeout(timeout);
+  }, [inputText, selectedAction, selectedTone, actions]);
+
   const loadClipboardText = async () => {
+    let clipboardText = '';
     try {
-      const clipboardText = await invoke<string>('get_clipboard_text');
-      setInputText(clipboardText || '');
+      clipboardText = await invoke<string>('get_clipboard_text');
     } catch (error) {
+      // Reading text fails when the clipboard holds an image (e.g. a screenshot)
       console.error('Failed to load clipboard text:', error);
-      setInputText('');
+    }
+    setInputText(clipboardText || '');
+
+    if (clipboardText) {
+      setInputImage(null);
+      return;
+    }
+
+    try {
+      const clipboardImage = await invoke<string | null>('get_clipboard_image');
+      setInputImage(clipboardImage);
+    } catch (error) {
+      console.error('Failed to load clipboard image:', error);
+      setInputImage(null);
     }
   };
 
-  const handleActionSelect = (action: 'proofread' | 'tone' | 'draft') => {
+  const handleActionSelect = (action: string) => {
     setSelectedAction(action);
   };
 
+  const localActions = actions.filter(action => action.kind === 'local');
+  const isLocalAction = localActions.some(action => action.id === selectedAction);
+  const selectedPipeline = selectedAction.startsWith(PIPELINE_PREFIX)
+    ? pipelines.find(pipeline => pipeline.id === selectedAction.slice(PIPELINE_PREFIX.length))
+    : undefined;
+  const isAi = (action: string) => (actions.find(a => a.id === action)?.kind ?? 'ai') === 'ai';
+
   const handleSendToAI = async () => {
-    if (!inputText.trim()) {
+    if (!inputText.trim() && !inputImage) {
       alert('Please enter some text to process');
       return;
     }
@@ -65,21 +232,26 @@ const Overlay: React.FC<OverlayProps> = () => {
       const apiKey = localStorage.getItem('openrouter_api_key') || '';
       const model = localStorage.getItem('selected_model') || '';
 
+      // Command and local actions run on this machine and need neither a model nor an API key
+      const needsModel = selectedPipeline
+        ? selectedPipeline.steps.some(step => isAi(step.action))
+        : isAi(selectedAction);
+
       // If no model is selected, show an error
-      if (!model) {
+      if (needsModel && !model) {
         alert('Please select a model in Settings first');
         setIsLoading(false);
         return;
       }
 
-      if (!apiKey) {
+      if (needsModel && !apiKey) {
         alert('Please set your OpenRouter API key in Settings first');
         setIsLoading(false);
         return;
       }
 
       // Validate API key format
-      if (!apiKey.startsWith('sk-or-v1-')) {
+      if (needsModel && !apiKey.startsWith('sk-or-v1-')) {
         alert('Invalid API key format. Please check your OpenRouter API key in Settings.');
         setIsLoading(false);
         return;
@@ -88,16 +260,22 @@ const Overlay: React.FC<OverlayProps> = () => {
       // Get max tokens setting
       const maxTokens = localStorage.getItem('max_tokens') || '2000';
 
-      const result = await invoke<string>('process_text_with_ai', {
-        text: inputText,
-        action: selectedAction,
-        model,
-        apiKey,
-        tone: selectedAction === 'tone' ? selectedTone : undefined,
-        maxTokens: parseInt(maxTokens)
-      });
+      const { text: result, usage, budget_warning, reasoning } = selectedPipeline
+        ? await runPipeline(selectedPipeline, model, apiKey, parseInt(maxTokens))
+        : await invoke<ActionOutput>('process_text_with_ai', {
+          text: inputText,
+          action: selectedAction,
+          model,
+          apiKey,
+          tone: selectedAction === 'tone' ? selectedTone : undefined,
+          maxTokens: parseInt(maxTokens),
+          image: inputImage ?? undefined
+        });
 
       setOutputText(result);
+      setOutputUsage(usage);
+      setBudgetWarning(budget_warning);
+      setReasoning(reasoning);
 
       if (autoCloseEnabled || selectedAction === 'proofread') {
         // Automatically copy result to clipboard
@@ -121,6 +299,10 @@ const Overlay: React.FC<OverlayProps> = () => {
           alert('Authentication failed. Please check your OpenRouter API key in Settings.');
         } else if (error.includes('403') || error.includes('Forbidden')) {
           alert('Access forbidden. Your API key may not have permission to use this model.');
+        } else if (error.includes('402')) {
+          alert('Your OpenRouter credits are exhausted. Add credits at openrouter.ai/credits and try again.');
+        } else if (error.includes('Spending limit reached')) {
+          alert(error);
         } else if (error.includes('429') || error.includes('rate limit')) {
           alert('Rate limit exceeded. Please try again later.');
         } else if (error.includes('network') || error.includes('connection')) {
@@ -136,6 +318,26 @@ const Overlay: React.FC<OverlayProps> = () => {
     }
   };
 
+  // A failed step fails the whole pipeline, like a failed action
+  const runPipeline = async (pipeline: Pipeline, model: string, apiKey: string, maxTokens: number): Promise<ActionOutput> => {
+    const result = await invoke<PipelineResult>('run_pipeline', {
+      pipelineId: pipeline.id,
+      text: inputText,
+      model,
+      apiKey,
+      maxTokens,
+    });
+    if (result.output === null) {
+      throw result.error ?? `Pipeline ${pipeline.name} failed`;
+    }
+    return {
+      text: result.output,
+      usage: totalUsage(result.steps.flatMap(step => (step.usage ? [step.usage] : []))),
+      budget_warning: null,
+      reasoning: null,
+    };
+  };
+
   const copyToClipboard = async () => {
     if (outputText) {
       try {
@@ -187,8 +389,78 @@ const Overlay: React.FC<OverlayProps> = () => {
             <PenTool size={12} className="action-button__icon" />
             Draft
           </button>
+          {localActions.length > 0 && (
+            <button
+              onClick={() => handleActionSelect(isLocalAction ? selectedAction : localActions[0].id)}
+              disabled={isLoading}
+              className={`action-button ${isLocalAction ? 'action-button--active' : ''}`}
+            >
+              <Wand2 size={12} className="action-button__icon" />
+              Transform
+            </button>
+          )}
+          {pipelines.length > 0 && (
+            <button
+              onClick={() => handleActionSelect(selectedPipeline ? selectedAction : `${PIPELINE_PREFIX}${pipelines[0].id}`)}
+              disabled={isLoading}
+              className={`action-button ${selectedPipeline ? 'action-button--active' : ''}`}
+            >
+              <Layers size={12} className="action-button__icon" />
+              Pipeline
+            </button>
+          )}
+          {actions
+            .filter(action => !BUILT_IN_ACTIONS.includes(action.id) && action.kind !== 'local')
+            .map(action => (
+              <button
+                key={action.id}
+                onClick={() => handleActionSelect(action.id)}
+                disabled={isLoading}
+                title={action.description}
+                className={`action-button ${selectedAction === action.id ? 'action-button--active' : ''}`}
+              >
+                {action.kind === 'command'
+                  ? <Terminal size={12} className="action-button__icon" />
+                  : <Sparkles size={12} className="action-button__icon" />}
+                {action.title}
+              </button>
+            ))}
         </div>
 
+        {isLocalAction && (
+          <div className="tone-selection">
+            <label className="tone-selection__label">Transform:</label>
+            <select
+              value={selectedAction}
+              onChange={(e) => handleActionSelect(e.target.value)}
+              className="tone-selection__select"
+            >
+              {localActions.map(action => (
+                <option key={action.id} value={action.id} title={action.description}>
+                  {action.title}
+                </option>
+              ))}
+            </select>
+          </div>
+        )}
+
+        {selectedPipeline && (
+          <div className="tone-selection">
+            <label className="tone-selection__label">Pipeline:</label>
+            <select
+              value={selectedAction}
+              onChange={(e) => handleActionSelect(e.target.value)}
+              className="tone-selection__select"
+            >
+              {pipelines.map(pipeline => (
+                <option key={pipeline.id} value={`${PIPELINE_PREFIX}${pipeline.id}`}>
+                  {pipeline.name}
+                </option>
+              ))}
+            </select>
+          </div>
+        )}
+
         {selectedAction === 'tone' && (
           <div className="tone-selection">
             <label className="tone-selection__label">Tone:</label>
@@ -220,13 +492,25 @@ const Overlay: React.FC<OverlayProps> = () => {
         )}
 
         <div className="input-section">
-          <label className="input-section__label">Input:</label>
+          <label className="input-section__label">
+            Input:
+            {estimate && <span className="input-section__estimate">{formatEstimate(estimate)}</span>}
+          </label>
+          {estimate?.warning && <div className="output-section__warning">{estimate.warning}</div>}
           <textarea
             value={inputText}
             onChange={(e) => setInputText(e.target.value)}
             placeholder="Enter text here or use selected text from clipboard..."
             className="input-section__textarea"
           />
+          {inputImage && (
+            <div className="input-section__image">
+              <img src={inputImage} alt="Clipboard image" className="input-section__image-preview" />
+              <button onClick={() => setInputImage(null)} className="copy-button">
+                Remove image
+              </button>
+            </div>
+          )}
         </div>
 
         {outputText && !(
@@ -234,7 +518,10 @@ const Overlay: React.FC<OverlayProps> = () => {
         ) && (
           <div className="output-section">
             <div className="output-section__header">
-              <label className="output-section__label">Output:</label>
+              <label className="output-section__label">
+                Output:
+                {outputUsage && <span className="output-section__usage">{formatUsage(outputUsage)}</span>}
+              </label>
               <button
                 onClick={copyToClipboard}
                 className={`copy-button ${copied ? 'copy-button--copied' : ''}`}
@@ -243,6 +530,13 @@ const Overlay: React.FC<OverlayProps> = () => {
                 {copied ? 'Copied!' : 'Copy'}
               </button>
             </div>
+            {budgetWarning && <div className="output-section__warning">{budgetWarning}</div>}
+            {reasoning && (
+              <details className="output-section__reasoning">
+                <summary>Reasoning</summary>
+                <div className="output-section__reasoning-text">{reasoning}</div>
+              </details>
+            )}
             <textarea
               value={outputText}
               readOnly
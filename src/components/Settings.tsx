@@ -1,15 +1,148 @@
 import React, { useState, useEffect } from 'react';
 import { invoke } from '@tauri-apps/api/core';
-import { Key, Loader2, Check, AlertCircle } from 'lucide-react';
+import { Key, Loader2, Check, AlertCircle, Star } from 'lucide-react';
 
 interface SettingsProps {}
 
+interface KeyStatus {
+  valid: boolean;
+  error: string | null;
+  label: string | null;
+  is_free_tier: boolean;
+  usage: number;
+  limit: number | null;
+  limit_remaining: number | null;
+  rate_limit: { requests: number; interval: string } | null;
+  credits_remaining: number | null;
+}
+
+const describeKeyStatus = (status: KeyStatus) => {
+  const parts = [];
+  if (status.credits_remaining !== null) {
+    parts.push(`$${status.credits_remaining.toFixed(2)} credits left`);
+  }
+  if (status.limit !== null && status.limit_remaining !== null) {
+    parts.push(`$${status.limit_remaining.toFixed(2)} of the key's $${status.limit.toFixed(2)} limit left`);
+  }
+  if (status.rate_limit) {
+    parts.push(`${status.rate_limit.requests} requests per ${status.rate_limit.interval}`);
+  }
+  if (status.is_free_tier) {
+    parts.push('free tier');
+  }
+  return parts.join(' · ');
+};
+
 interface Model {
   id: string;
   name: string;
   description?: string;
 }
 
+interface SamplingParams {
+  temperature: number | null;
+  top_p: number | null;
+  frequency_penalty: number | null;
+  presence_penalty: number | null;
+  stop: string[];
+  seed: number | null;
+  reasoning: ReasoningSettings | null;
+}
+
+interface ReasoningSettings {
+  effort: 'low' | 'medium' | 'high' | null;
+  max_tokens: number | null;
+  exclude: boolean;
+}
+
+interface SamplingSettings {
+  defaults: SamplingParams;
+  actions: Record<string, SamplingParams>;
+}
+
+const emptySampling: SamplingParams = {
+  temperature: null,
+  top_p: null,
+  frequency_penalty: null,
+  presence_penalty: null,
+  stop: [],
+  seed: null,
+  reasoning: null,
+};
+
+const samplingFields: { key: 'temperature' | 'top_p' | 'frequency_penalty' | 'presence_penalty' | 'seed'; label: string; step: string }[] = [
+  { key: 'temperature', label: 'Temperature', step: '0.1' },
+  { key: 'top_p', label: 'Top P', step: '0.05' },
+  { key: 'frequency_penalty', label: 'Frequency Penalty', step: '0.1' },
+  { key: 'presence_penalty', label: 'Presence Penalty', step: '0.1' },
+  { key: 'seed', label: 'Seed', step: '1' },
+];
+
+interface ProviderPreferences {
+  order: string[];
+  allow_fallbacks: boolean | null;
+  data_collection: 'allow' | 'deny' | null;
+  require_parameters: boolean | null;
+  quantizations: string[];
+}
+
+// Comma-separated text input to list and back
+const splitList = (value: string) => value.split(',').map(item => item.trim()).filter(item => item !== '');
+
+type BudgetScope = 'global' | 'profile';
+
+interface BudgetSettings {
+  daily_limit: number | null;
+  monthly_limit: number | null;
+  warn_threshold: number;
+}
+
+interface BudgetStatus {
+  scope: BudgetScope;
+  period: 'day' | 'week' | 'month';
+  limit: number;
+  spent: number;
+  remaining: number;
+  warning: boolean;
+  exceeded: boolean;
+  unpriced_requests: number;
+  unpriced_counted: boolean;
+}
+
+const emptyBudget: BudgetSettings = { daily_limit: null, monthly_limit: null, warn_threshold: 0.8 };
+
+const describeBudgetStatus = (status: BudgetStatus) => {
+  const period = status.period === 'day' ? 'Today' : status.period === 'week' ? 'This week' : 'This month';
+  const scope = status.scope === 'global' ? 'all profiles' : 'this profile';
+  let text = `${period} (${scope}): $${status.spent.toFixed(2)} of $${status.limit.toFixed(2)} spent, $${status.remaining.toFixed(2)} left`;
+  if (status.unpriced_requests > 0 && status.unpriced_counted) {
+    text += `, counting ${status.unpriced_requests} calls without a known price at the highest model prices`;
+  } else if (status.unpriced_requests > 0) {
+    text += `, not counting ${status.unpriced_requests} calls without a known price until the model list is loaded`;
+  }
+  return text;
+};
+
+interface PipelineStep {
+  action: string;
+  tone: string | null;
+  model: string | null;
+  max_tokens: number | null;
+}
+
+interface Pipeline {
+  id: string;
+  name: string;
+  steps: PipelineStep[];
+}
+
+const newPipelineStep = (): PipelineStep => ({ action: 'proofread', tone: null, model: null, max_tokens: null });
+
+interface ModelPreferences {
+  favorites: string[];
+  aliases: Record<string, string>;
+}
+
 const Settings: React.FC<SettingsProps> = () => {
   const [apiKey, setApiKey] = useState('');
   const [models, setModels] = useState<Model[]>([]);
@@ -19,6 +152,33 @@ const Settings: React.FC<SettingsProps> = () => {
   const [maxTokens, setMaxTokens] = useState('2000');
   const [defaultTone, setDefaultTone] = useState('professional');
   const [isLoaded, setIsLoaded] = useState(false);
+  const [keyStatus, setKeyStatus] = useState<KeyStatus | null>(null);
+  const [modelQuery, setModelQuery] = useState('');
+  const [freeOnly, setFreeOnly] = useState(false);
+  const [imageInput, setImageInput] = useState(false);
+  const [filteredModels, setFilteredModels] = useState<Model[]>([]);
+  const [preferences, setPreferences] = useState<ModelPreferences>({ favorites: [], aliases: {} });
+  const [aliasName, setAliasName] = useState('');
+  const [aliasModel, setAliasModel] = useState('');
+  const [sampling, setSampling] = useState<SamplingSettings>({ defaults: emptySampling, actions: {} });
+  // Action whose sampling settings are being edited, '' for the defaults
+  const [samplingAction, setSamplingAction] = useState('');
+  const [provider, setProvider] = useState<ProviderPreferences>({
+    order: [],
+    allow_fallbacks: null,
+    data_collection: null,
+    require_parameters: null,
+    quantizations: [],
+  });
+  const [providerOrder, setProviderOrder] = useState('');
+  const [quantizations, setQuantizations] = useState('');
+  const [aiActions, setAiActions] = useState<{ id: string; title: string }[]>([]);
+  const [allActions, setAllActions] = useState<{ id: string; title: string }[]>([]);
+  const [pipelines, setPipelines] = useState<Pipeline[]>([]);
+  const [budgetScope, setBudgetScope] = useState<BudgetScope>('global');
+  const [budget, setBudget] = useState<BudgetSettings>(emptyBudget);
+  const [budgetStatus, setBudgetStatus] = useState<BudgetStatus[]>([]);
+  const [budgetError, setBudgetError] = useState<string | null>(null);
 
   useEffect(() => {
     // Load saved settings
@@ -51,7 +211,43 @@ const Settings: React.FC<SettingsProps> = () => {
       const savedDefaultTone = localStorage.getItem('default_tone') || 'professional';
 
       setApiKey(savedApiKey);
+      if (savedApiKey) {
+        checkKey(savedApiKey);
+      }
       setSelectedModel(savedModel);
+      // Models picked before the selection was mirrored into the store only exist in localStorage
+      if (savedModel) {
+        invoke<string>('get_default_model')
+          .then((storedModel) => {
+            if (storedModel !== savedModel) {
+              return invoke('set_default_model', { model: savedModel });
+            }
+          })
+          .catch(console.error);
+      }
+      invoke<ModelPreferences>('get_model_preferences')
+        .then(setPreferences)
+        .catch((error) => console.error('Failed to load model preferences:', error));
+      invoke<SamplingSettings>('get_sampling')
+        .then(setSampling)
+        .catch((error) => console.error('Failed to load sampling settings:', error));
+      invoke<ProviderPreferences>('get_provider_preferences')
+        .then((preferences) => {
+          setProvider(preferences);
+          setProviderOrder(preferences.order.join(', '));
+          setQuantizations(preferences.quantizations.join(', '));
+        })
+        .catch((error) => console.error('Failed to load provider preferences:', error));
+      invoke<{ id: string; title: string; kind: string }[]>('list_actions')
+        .then((actions) => {
+          setAiActions(actions.filter(action => action.kind === 'ai'));
+          setAllActions(actions);
+        })
+        .catch(console.error);
+      invoke<Pipeline[]>('get_pipelines')
+        .then(setPipelines)
+        .catch((error) => console.error('Failed to load pipelines:', error));
+      loadBudgetStatus();
       setMaxTokens(savedMaxTokens);
       setDefaultTone(savedDefaultTone);
     } catch (e) {
@@ -61,7 +257,36 @@ const Settings: React.FC<SettingsProps> = () => {
     }
   };
 
-  const saveApiKey = () => {
+  const loadBudgetStatus = () => {
+    invoke<BudgetStatus[]>('get_budget_status')
+      .then((statuses) => {
+        setBudgetStatus(statuses);
+        setBudgetError(null);
+      })
+      .catch((error) => setBudgetError(String(error)));
+  };
+
+  // The global and the profile budget are edited one at a time
+  useEffect(() => {
+    invoke<BudgetSettings | null>('get_budget', { scope: budgetScope })
+      .then((saved) => setBudget(saved ?? emptyBudget))
+      .catch((error) => console.error('Failed to load budget:', error));
+  }, [budgetScope]);
+
+  // Ask OpenRouter whether the key works and how much credit is left
+  const checkKey = async (key: string) => {
+    try {
+      const status = await invoke<KeyStatus>('get_key_status', { apiKey: key });
+      setKeyStatus(status);
+      return status;
+    } catch (error) {
+      console.error('Failed to check API key:', error);
+      setKeyStatus(null);
+      return null;
+    }
+  };
+
+  const saveApiKey = async () => {
     if (!apiKey.trim()) {
       setMessage({ type: 'error', text: 'Please enter an API key' });
       return;
@@ -73,6 +298,12 @@ const Settings: React.FC<SettingsProps> = () => {
       return;
     }
 
+    const status = await checkKey(apiKey);
+    if (status && !status.valid) {
+      setMessage({ type: 'error', text: `OpenRouter rejected this API key: ${status.error}` });
+      return;
+    }
+
     // Save via Tauri store so the backend can access it and to avoid frontend storage permission issues
     invoke('set_api_key', { apiKey })
       .then(() => {
@@ -120,6 +351,8 @@ const Settings: React.FC<SettingsProps> = () => {
         const defaultModel = freeModel || geminiModel || formattedModels[0];
         setSelectedModel(defaultModel.id);
         localStorage.setItem('selected_model', defaultModel.id);
+        // Mirror the selection into the Tauri store for the CLI and HTTP API
+        invoke('set_default_model', { model: defaultModel.id }).catch(console.error);
       }
 
       setMessage({ type: 'success', text: `Loaded ${formattedModels.length} models` });
@@ -131,6 +364,138 @@ const Settings: React.FC<SettingsProps> = () => {
     }
   };
 
+  const savePreferences = (next: ModelPreferences) => {
+    invoke('set_model_preferences', { preferences: next })
+      .then(() => setPreferences(next))
+      .catch((error) => setMessage({ type: 'error', text: `Failed to save model preferences: ${error}` }));
+  };
+
+  const toggleFavorite = () => {
+    if (!selectedModel) {
+      return;
+    }
+    const favorites = preferences.favorites.includes(selectedModel)
+      ? preferences.favorites.filter(id => id !== selectedModel)
+      : [...preferences.favorites, selectedModel];
+    savePreferences({ ...preferences, favorites });
+  };
+
+  const addAlias = () => {
+    const name = aliasName.trim();
+    const model = aliasModel.trim();
+    if (!name || !model) {
+      setMessage({ type: 'error', text: 'Enter an alias and the model it stands for' });
+      return;
+    }
+    savePreferences({ ...preferences, aliases: { ...preferences.aliases, [name]: model } });
+    setAliasName('');
+    setAliasModel('');
+  };
+
+  const removeAlias = (name: string) => {
+    const aliases = { ...preferences.aliases };
+    delete aliases[name];
+    savePreferences({ ...preferences, aliases });
+  };
+
+  const currentSampling = samplingAction
+    ? sampling.actions[samplingAction] ?? emptySampling
+    : sampling.defaults;
+
+  const updateSampling = (params: SamplingParams) => {
+    setSampling(samplingAction
+      ? { ...sampling, actions: { ...sampling.actions, [samplingAction]: params } }
+      : { ...sampling, defaults: params });
+  };
+
+  const updateReasoning = (changes: Partial<ReasoningSettings>) => {
+    const reasoning = { effort: null, max_tokens: null, exclude: false, ...currentSampling.reasoning, ...changes };
+    const unset = reasoning.effort === null && reasoning.max_tokens === null && !reasoning.exclude;
+    updateSampling({ ...currentSampling, reasoning: unset ? null : reasoning });
+  };
+
+  const saveSampling = () => {
+    const clean = (params: SamplingParams) => ({ ...params, stop: params.stop.filter(stop => stop !== '') });
+    const settings = {
+      defaults: clean(sampling.defaults),
+      actions: Object.fromEntries(Object.entries(sampling.actions).map(([id, params]) => [id, clean(params)])),
+    };
+    invoke('set_sampling', { settings })
+      .then(() => setMessage({ type: 'success', text: 'Sampling settings saved!' }))
+      .catch((error) => setMessage({ type: 'error', text: `Failed to save sampling settings: ${error}` }));
+  };
+
+  const saveProvider = () => {
+    const preferences = { ...provider, order: splitList(providerOrder), quantizations: splitList(quantizations) };
+    invoke('set_provider_preferences', { preferences })
+      .then(() => {
+        setProvider(preferences);
+        setMessage({ type: 'success', text: 'Provider preferences saved!' });
+      })
+      .catch((error) => setMessage({ type: 'error', text: `Failed to save provider preferences: ${error}` }));
+  };
+
+  const updatePipeline = (index: number, changes: Partial<Pipeline>) => {
+    setPipelines(pipelines.map((pipeline, i) => (i === index ? { ...pipeline, ...changes } : pipeline)));
+  };
+
+  const updatePipelineStep = (index: number, stepIndex: number, changes: Partial<PipelineStep>) => {
+    const steps = pipelines[index].steps.map((step, i) => (i === stepIndex ? { ...step, ...changes } : step));
+    updatePipeline(index, { steps });
+  };
+
+  const addPipeline = () => {
+    const number = pipelines.length + 1;
+    setPipelines([...pipelines, { id: `pipeline-${number}`, name: `Pipeline ${number}`, steps: [newPipelineStep()] }]);
+  };
+
+  const savePipelines = () => {
+    const cleaned = pipelines.map((pipeline) => ({
+      ...pipeline,
+      id: pipeline.id.trim(),
+      steps: pipeline.steps.map((step) => ({
+        ...step,
+        tone: step.action === 'tone' ? step.tone || defaultTone : null,
+        model: step.model?.trim() || null,
+      })),
+    }));
+    invoke('set_pipelines', { pipelines: cleaned })
+      .then(() => {
+        setPipelines(cleaned);
+        setMessage({ type: 'success', text: 'Pipelines saved!' });
+      })
+      .catch((error) => setMessage({ type: 'error', text: `Failed to save pipelines: ${error}` }));
+  };
+
+  const saveBudget = () => {
+    invoke('set_budget', { scope: budgetScope, budget })
+      .then(() => {
+        setMessage({ type: 'success', text: 'Budget saved!' });
+        loadBudgetStatus();
+      })
+      .catch((error) => setMessage({ type: 'error', text: `Failed to save budget: ${error}` }));
+  };
+
+  // Filtering runs against the backend's cached catalog, so it doesn't refetch the model list
+  useEffect(() => {
+    if (models.length === 0) {
+      return;
+    }
+    const filter = {
+      query: modelQuery || null,
+      free_only: freeOnly,
+      input_modality: imageInput ? 'image' : null,
+    };
+    invoke<Model[]>('search_models', { apiKey, filter })
+      .then(setFilteredModels)
+      .catch((error) => console.error('Failed to filter models:', error));
+  }, [models, modelQuery, freeOnly, imageInput]);
+
+  // Keep the current selection visible even when the filter excludes it
+  const modelOptions = filteredModels.some(m => m.id === selectedModel)
+    ? filteredModels
+    : [...filteredModels, ...models.filter(m => m.id === selectedModel)];
+
   const toneOptions = [
     'professional',
     'casual',
@@ -199,6 +564,13 @@ const Settings: React.FC<SettingsProps> = () => {
             {isLoadingModels ? "Loading Models..." : "Load Available Models"}
           </button>
 
+          {keyStatus && (
+            <div className="settings__current-model">
+              <strong>Account:</strong>{" "}
+              {keyStatus.valid ? describeKeyStatus(keyStatus) || 'Key is valid' : 'Invalid API key'}
+            </div>
+          )}
+
           {selectedModel && (
             <div className="settings__current-model">
               <strong>Current Model:</strong> {selectedModel}
@@ -210,26 +582,120 @@ const Settings: React.FC<SettingsProps> = () => {
         {models.length > 0 && (
           <section className="settings__section">
             <h3 className="settings__section-title">Model Selection</h3>
+            <div className="settings__select-container">
+              <input
+                type="text"
+                value={modelQuery}
+                onChange={(e) => setModelQuery(e.target.value)}
+                placeholder="Search models..."
+                className="settings__model-select"
+              />
+              <label className="checkbox-label">
+                <input
+                  type="checkbox"
+                  checked={freeOnly}
+                  onChange={(e) => setFreeOnly(e.target.checked)}
+                  className="checkbox"
+                />
+                Free models only
+              </label>
+              <label className="checkbox-label">
+                <input
+                  type="checkbox"
+                  checked={imageInput}
+                  onChange={(e) => setImageInput(e.target.checked)}
+                  className="checkbox"
+                />
+                Accepts images
+              </label>
+            </div>
             <div className="settings__select-container">
               <select
                 value={selectedModel}
                 onChange={(e) => {
                   setSelectedModel(e.target.value)
                   localStorage.setItem('selected_model', e.target.value);
+                  invoke('set_default_model', { model: e.target.value }).catch(console.error);
                   setMessage({ type: 'success', text: 'Model selection saved!' });
                 }}
                 className="settings__model-select"
               >
                 <option value="">Select a model...</option>
-                {[...models]
-                  .sort((a, b) => a.name.localeCompare(b.name))
-                  .map((model) => (
-                    <option key={model.id} value={model.id}>
-                      {model.name}{" "}
-                      {model.description ? ` - ${model.description}` : ""}
-                    </option>
-                  ))}
+                {Object.keys(preferences.aliases).length > 0 && (
+                  <optgroup label="Aliases">
+                    {Object.entries(preferences.aliases).map(([alias, id]) => (
+                      <option key={`alias:${alias}`} value={alias}>
+                        {alias} → {id}
+                      </option>
+                    ))}
+                  </optgroup>
+                )}
+                {preferences.favorites.length > 0 && (
+                  <optgroup label="Favorites">
+                    {preferences.favorites.map((id) => (
+                      <option key={`favorite:${id}`} value={id}>
+                        {models.find(m => m.id === id)?.name || id}
+                      </option>
+                    ))}
+                  </optgroup>
+                )}
+                <optgroup label="All models">
+                  {[...modelOptions]
+                    .sort((a, b) => a.name.localeCompare(b.name))
+                    .map((model) => (
+                      <option key={model.id} value={model.id}>
+                        {model.name}{" "}
+                        {model.description ? ` - ${model.description}` : ""}
+                      </option>
+                    ))}
+                </optgroup>
               </select>
+              <button onClick={toggleFavorite} disabled={!selectedModel} className="settings__save-button">
+                <Star size={12} className="settings__button-icon" />
+                {preferences.favorites.includes(selectedModel) ? 'Unfavorite' : 'Favorite'}
+              </button>
+            </div>
+
+            <div className="settings__setting-row">
+              <label className="settings__setting-label">
+                Model Aliases:
+              </label>
+              {Object.entries(preferences.aliases).map(([alias, id]) => (
+                <div key={alias} className="settings__current-model">
+                  <strong>{alias}</strong> → {id}{" "}
+                  <button onClick={() => removeAlias(alias)} className="settings__save-button">
+                    Remove
+                  </button>
+                </div>
+              ))}
+              <div className="settings__select-container">
+                <input
+                  type="text"
+                  value={aliasName}
+                  onChange={(e) => setAliasName(e.target.value)}
+                  placeholder="Alias, e.g. fast"
+                  className="settings__model-select"
+                />
+                <input
+                  type="text"
+                  value={aliasModel}
+                  onChange={(e) => setAliasModel(e.target.value)}
+                  placeholder="Model id"
+                  list="settings-model-ids"
+                  className="settings__model-select"
+                />
+                <datalist id="settings-model-ids">
+                  {models.map((model) => (
+                    <option key={model.id} value={model.id} />
+                  ))}
+                </datalist>
+                <button onClick={addAlias} className="settings__save-button">
+                  Add Alias
+                </button>
+              </div>
+              <div className="settings__setting-description">
+                An alias works anywhere a model id does, including the CLI and custom actions. Pointing it at a new model updates everything that uses it.
+              </div>
             </div>
           </section>
         )}
@@ -281,6 +747,315 @@ const Settings: React.FC<SettingsProps> = () => {
               For "Change Tone" action
             </div>
           </div>
+
+          {/* Sampling Settings */}
+          <div className="settings__setting-row">
+            <label className="settings__setting-label">
+              Sampling:
+            </label>
+            <div className="settings__select-container">
+              <select
+                value={samplingAction}
+                onChange={(e) => setSamplingAction(e.target.value)}
+                className="settings__model-select"
+              >
+                <option value="">All actions</option>
+                {aiActions.map(action => (
+                  <option key={action.id} value={action.id}>
+                    {action.title}
+                  </option>
+                ))}
+              </select>
+            </div>
+            {samplingFields.map(field => (
+              <div key={field.key} className="settings__select-container">
+                <input
+                  type="number"
+                  step={field.step}
+                  value={currentSampling[field.key] ?? ''}
+                  onChange={(e) => updateSampling({
+                    ...currentSampling,
+                    [field.key]: e.target.value === '' ? null : Number(e.target.value),
+                  })}
+                  placeholder={field.label}
+                  className="settings__model-select"
+                />
+              </div>
+            ))}
+            <div className="settings__select-container">
+              <textarea
+                value={currentSampling.stop.join('\n')}
+                onChange={(e) => updateSampling({ ...currentSampling, stop: e.target.value.split('\n') })}
+                placeholder="Stop sequences, one per line"
+                rows={2}
+                className="settings__model-select"
+              />
+            </div>
+            <div className="settings__select-container">
+              <select
+                value={currentSampling.reasoning?.effort ?? ''}
+                onChange={(e) => updateReasoning({
+                  effort: e.target.value === '' ? null : e.target.value as ReasoningSettings['effort'],
+                })}
+                className="settings__model-select"
+              >
+                <option value="">Model's default reasoning effort</option>
+                <option value="low">Low reasoning effort</option>
+                <option value="medium">Medium reasoning effort</option>
+                <option value="high">High reasoning effort</option>
+              </select>
+            </div>
+            <div className="settings__select-container">
+              <input
+                type="number"
+                min="1"
+                value={currentSampling.reasoning?.max_tokens ?? ''}
+                onChange={(e) => updateReasoning({
+                  max_tokens: e.target.value === '' ? null : Number(e.target.value),
+                })}
+                placeholder="Reasoning token budget"
+                className="settings__model-select"
+              />
+            </div>
+            <label className="checkbox-label">
+              <input
+                type="checkbox"
+                checked={currentSampling.reasoning?.exclude ?? false}
+                onChange={(e) => updateReasoning({ exclude: e.target.checked })}
+                className="checkbox"
+              />
+              Hide the model's reasoning
+            </label>
+            <button onClick={saveSampling} className="settings__save-button">
+              Save Sampling
+            </button>
+            <div className="settings__setting-description">
+              Empty fields use the defaults. Proofread uses temperature 0.2 and other actions 0.7 unless set here. Reasoning uses either an effort or a token budget, and max tokens is raised so the answer keeps its own room.
+            </div>
+          </div>
+
+          {/* Provider Routing Settings */}
+          <div className="settings__setting-row">
+            <label className="settings__setting-label">
+              Provider Routing:
+            </label>
+            <div className="settings__select-container">
+              <input
+                type="text"
+                value={providerOrder}
+                onChange={(e) => setProviderOrder(e.target.value)}
+                placeholder="Preferred providers, e.g. anthropic, deepinfra"
+                className="settings__model-select"
+              />
+            </div>
+            <div className="settings__select-container">
+              <input
+                type="text"
+                value={quantizations}
+                onChange={(e) => setQuantizations(e.target.value)}
+                placeholder="Allowed quantizations, e.g. fp8, bf16"
+                className="settings__model-select"
+              />
+            </div>
+            <div className="settings__select-container">
+              <select
+                value={provider.allow_fallbacks === null ? '' : String(provider.allow_fallbacks)}
+                onChange={(e) => setProvider({
+                  ...provider,
+                  allow_fallbacks: e.target.value === '' ? null : e.target.value === 'true',
+                })}
+                className="settings__model-select"
+              >
+                <option value="">Default fallback behavior</option>
+                <option value="true">Fall back to other providers</option>
+                <option value="false">Only use the preferred providers</option>
+              </select>
+            </div>
+            <label className="checkbox-label">
+              <input
+                type="checkbox"
+                checked={provider.data_collection === 'deny'}
+                onChange={(e) => setProvider({ ...provider, data_collection: e.target.checked ? 'deny' : null })}
+                className="checkbox"
+              />
+              Deny providers that store or train on prompts
+            </label>
+            <label className="checkbox-label">
+              <input
+                type="checkbox"
+                checked={provider.require_parameters === true}
+                onChange={(e) => setProvider({ ...provider, require_parameters: e.target.checked ? true : null })}
+                className="checkbox"
+              />
+              Require providers to support every request parameter
+            </label>
+            <button onClick={saveProvider} className="settings__save-button">
+              Save Routing
+            </button>
+            <div className="settings__setting-description">
+              Sent as OpenRouter's provider preferences with every request, including those proxied by the HTTP API
+            </div>
+          </div>
+
+          {/* Pipelines */}
+          <div className="settings__setting-row">
+            <label className="settings__setting-label">
+              Pipelines:
+            </label>
+            {pipelines.map((pipeline, index) => (
+              <div key={index} className="settings__setting-row">
+                <div className="settings__select-container">
+                  <input
+                    type="text"
+                    value={pipeline.name}
+                    onChange={(e) => updatePipeline(index, { name: e.target.value })}
+                    placeholder="Name"
+                    className="settings__model-select"
+                  />
+                  <input
+                    type="text"
+                    value={pipeline.id}
+                    onChange={(e) => updatePipeline(index, { id: e.target.value })}
+                    placeholder="Id, used by the CLI and HTTP API"
+                    className="settings__model-select"
+                  />
+                  <button
+                    onClick={() => setPipelines(pipelines.filter((_, i) => i !== index))}
+                    className="settings__save-button"
+                  >
+                    Remove
+                  </button>
+                </div>
+                {pipeline.steps.map((step, stepIndex) => (
+                  <div key={stepIndex} className="settings__select-container">
+                    <select
+                      value={step.action}
+                      onChange={(e) => updatePipelineStep(index, stepIndex, { action: e.target.value })}
+                      className="settings__model-select"
+                    >
+                      {allActions.map(action => (
+                        <option key={action.id} value={action.id}>
+                          {stepIndex + 1}. {action.title}
+                        </option>
+                      ))}
+                    </select>
+                    {step.action === 'tone' && (
+                      <select
+                        value={step.tone ?? defaultTone}
+                        onChange={(e) => updatePipelineStep(index, stepIndex, { tone: e.target.value })}
+                        className="settings__model-select"
+                      >
+                        {toneOptions.map(tone => (
+                          <option key={tone} value={tone}>
+                            {tone.charAt(0).toUpperCase() + tone.slice(1)}
+                          </option>
+                        ))}
+                      </select>
+                    )}
+                    <input
+                      type="text"
+                      value={step.model ?? ''}
+                      onChange={(e) => updatePipelineStep(index, stepIndex, { model: e.target.value })}
+                      placeholder="Model (default model if empty)"
+                      list="settings-model-ids"
+                      className="settings__model-select"
+                    />
+                    <button
+                      onClick={() => updatePipeline(index, { steps: pipeline.steps.filter((_, i) => i !== stepIndex) })}
+                      className="settings__save-button"
+                    >
+                      Remove Step
+                    </button>
+                  </div>
+                ))}
+                <button
+                  onClick={() => updatePipeline(index, { steps: [...pipeline.steps, newPipelineStep()] })}
+                  className="settings__save-button"
+                >
+                  Add Step
+                </button>
+              </div>
+            ))}
+            <div className="settings__select-container">
+              <button onClick={addPipeline} className="settings__save-button">
+                Add Pipeline
+              </button>
+              <button onClick={savePipelines} className="settings__save-button">
+                Save Pipelines
+              </button>
+            </div>
+            <div className="settings__setting-description">
+              A pipeline runs its steps in order, each on the previous step's output, and shows up in the overlay next to the actions.
+            </div>
+          </div>
+
+          {/* Budget Settings */}
+          <div className="settings__setting-row">
+            <label className="settings__setting-label">
+              Budget:
+            </label>
+            <div className="settings__select-container">
+              <select
+                value={budgetScope}
+                onChange={(e) => setBudgetScope(e.target.value as BudgetScope)}
+                className="settings__model-select"
+              >
+                <option value="global">All profiles and the CLI</option>
+                <option value="profile">This profile only</option>
+              </select>
+            </div>
+            <div className="settings__select-container">
+              <input
+                type="number"
+                min="0"
+                step="0.5"
+                value={budget.daily_limit ?? ''}
+                onChange={(e) => setBudget({ ...budget, daily_limit: e.target.value === '' ? null : Number(e.target.value) })}
+                placeholder="Daily limit in USD"
+                className="settings__model-select"
+              />
+            </div>
+            <div className="settings__select-container">
+              <input
+                type="number"
+                min="0"
+                step="1"
+                value={budget.monthly_limit ?? ''}
+                onChange={(e) => setBudget({ ...budget, monthly_limit: e.target.value === '' ? null : Number(e.target.value) })}
+                placeholder="Monthly limit in USD"
+                className="settings__model-select"
+              />
+            </div>
+            <div className="settings__select-container">
+              <input
+                type="number"
+                min="0"
+                max="100"
+                step="5"
+                value={Math.round(budget.warn_threshold * 100)}
+                onChange={(e) => setBudget({ ...budget, warn_threshold: Number(e.target.value) / 100 })}
+                placeholder="Warn at % of a limit"
+                className="settings__model-select"
+              />
+            </div>
+            <button onClick={saveBudget} className="settings__save-button">
+              Save Budget
+            </button>
+            {budgetStatus.map((status) => (
+              <div key={`${status.scope}:${status.period}`} className="settings__current-model">
+                <strong>{status.exceeded ? 'Limit reached' : status.warning ? 'Almost spent' : 'Remaining'}:</strong>{" "}
+                {describeBudgetStatus(status)}
+              </div>
+            ))}
+            {budgetError && (
+              <div className="settings__current-model">
+                <strong>Budget:</strong> {budgetError}
+              </div>
+            )}
+            <div className="settings__setting-description">
+              Model calls are refused once a limit is reached. Empty limits don't apply, and results carry a warning past the given percentage of a limit.
+            </div>
+          </div>
         </section>
       </div>
     </div>